@@ -2,13 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+#[cfg(windows)]
 use std::path::Path;
 
+#[cfg(windows)]
 use win7_notifications::{Notification, Timeout};
+#[cfg(windows)]
 use winit::{
     event::{Event, StartCause},
     event_loop::{ControlFlow, EventLoop},
 };
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example only runs on Windows.");
+}
+
+#[cfg(windows)]
 fn main() {
     let event_loop = EventLoop::new().unwrap();
     let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/icon.png");
@@ -32,6 +42,7 @@ fn main() {
         .unwrap();
 }
 
+#[cfg(windows)]
 fn load_icon(path: &Path) -> (Vec<u8>, u32, u32) {
     let image = image::open(path)
         .expect("Failed to open icon path")
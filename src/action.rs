@@ -0,0 +1,15 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// Maximum number of actions a notification can have, like Windows 10 toasts.
+pub(crate) const MAX_ACTIONS: usize = 5;
+
+/// A button shown at the bottom of a notification, see [`Notification::action`](crate::Notification::action).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    /// The identifier reported when the button is clicked.
+    pub id: String,
+    /// The text of the button.
+    pub label: String,
+}
@@ -0,0 +1,214 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::time::Duration;
+
+use windows_sys::Win32::Foundation::RECT;
+
+/// Interval between two animation frames, about 60 frames per second.
+pub(crate) const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Used when a notification slides in from the edge of the screen.
+pub(crate) const SLIDE_IN: Timeline = Timeline {
+    duration: Duration::from_millis(300),
+    easing: Easing::EaseOutCubic,
+};
+
+/// Used when a notification fades out before being closed.
+pub(crate) const FADE_OUT: Timeline = Timeline {
+    duration: Duration::from_millis(200),
+    easing: Easing::EaseInQuad,
+};
+
+/// Used when a notification glides to its new position in the stack.
+pub(crate) const GLIDE: Timeline = Timeline {
+    duration: Duration::from_millis(200),
+    easing: Easing::EaseOutCubic,
+};
+
+/// Maps the linear progress of an animation to the progress of the animated value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Easing {
+    /// Starts slowly and accelerates.
+    EaseInQuad,
+    /// Starts fast and decelerates.
+    EaseOutCubic,
+}
+
+impl Easing {
+    /// Applies the easing to `t`, clamped to `0.0..=1.0`.
+    pub(crate) fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+        }
+    }
+}
+
+/// The duration and easing of an animation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Timeline {
+    pub(crate) duration: Duration,
+    pub(crate) easing: Easing,
+}
+
+impl Timeline {
+    /// Returns the eased progress of the animation, in `0.0..=1.0`, after `elapsed`.
+    pub(crate) fn progress(&self, elapsed: Duration) -> f64 {
+        if self.duration.as_nanos() == 0 {
+            return 1.0;
+        }
+        self.easing
+            .apply(elapsed.as_secs_f64() / self.duration.as_secs_f64())
+    }
+
+    pub(crate) fn is_finished(&self, elapsed: Duration) -> bool {
+        elapsed >= self.duration
+    }
+}
+
+/// An integer value animated from `from` to `to` along a [`Timeline`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Tween {
+    pub(crate) from: i32,
+    pub(crate) to: i32,
+    pub(crate) timeline: Timeline,
+}
+
+impl Tween {
+    pub(crate) fn new(from: i32, to: i32, timeline: Timeline) -> Tween {
+        Tween { from, to, timeline }
+    }
+
+    /// Returns the value after `elapsed`, rounded to the nearest integer.
+    pub(crate) fn value(&self, elapsed: Duration) -> i32 {
+        let progress = self.timeline.progress(elapsed);
+        self.from + ((self.to - self.from) as f64 * progress).round() as i32
+    }
+
+    pub(crate) fn is_finished(&self, elapsed: Duration) -> bool {
+        self.timeline.is_finished(elapsed)
+    }
+}
+
+/// Duration of a sweep of the segment of an indeterminate progress bar.
+const INDETERMINATE_CYCLE: Duration = Duration::from_millis(1500);
+
+/// Returns the `(left, right)` bounds of the moving segment of an indeterminate progress bar
+/// `width` pixels wide, `elapsed` after the start of the animation.
+///
+/// The segment, a quarter of the bar, enters from the left and leaves on the right in a loop.
+pub(crate) fn indeterminate_segment(width: i32, elapsed: Duration) -> (i32, i32) {
+    let segment = width / 4;
+    let cycle = INDETERMINATE_CYCLE.as_millis();
+    let t = (elapsed.as_millis() % cycle) as f64 / cycle as f64;
+    let left = -segment + ((width + segment) as f64 * t).round() as i32;
+    (left.max(0), (left + segment).min(width))
+}
+
+/// Returns the position a notification slides in from to reach `target`,
+/// just outside of the closest left or right edge of the work area.
+pub(crate) fn slide_in_origin(target: RECT, work_area: RECT) -> (i32, i32) {
+    let width = target.right - target.left;
+    if target.left - work_area.left < work_area.right - target.right {
+        (work_area.left - width, target.top)
+    } else {
+        (work_area.right, target.top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn easing_bounds() {
+        for easing in [Easing::EaseInQuad, Easing::EaseOutCubic] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(-1.0), 0.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn easing_curves() {
+        assert_eq!(Easing::EaseInQuad.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOutCubic.apply(0.5), 0.875);
+    }
+
+    #[test]
+    fn timeline_progress() {
+        let timeline = Timeline {
+            duration: ms(200),
+            easing: Easing::EaseInQuad,
+        };
+        assert_eq!(timeline.progress(ms(0)), 0.0);
+        assert_eq!(timeline.progress(ms(100)), 0.25);
+        assert_eq!(timeline.progress(ms(400)), 1.0);
+        assert!(!timeline.is_finished(ms(199)));
+        assert!(timeline.is_finished(ms(200)));
+    }
+
+    #[test]
+    fn zero_duration_timeline_is_done() {
+        let timeline = Timeline {
+            duration: ms(0),
+            easing: Easing::EaseOutCubic,
+        };
+        assert_eq!(timeline.progress(ms(0)), 1.0);
+        assert!(timeline.is_finished(ms(0)));
+    }
+
+    #[test]
+    fn tween_values() {
+        let fade = Tween::new(255, 0, FADE_OUT);
+        assert_eq!(fade.value(ms(0)), 255);
+        assert_eq!(fade.value(ms(100)), 191);
+        assert_eq!(fade.value(ms(200)), 0);
+        assert_eq!(fade.value(ms(1000)), 0);
+
+        let glide = Tween::new(100, -100, GLIDE);
+        assert_eq!(glide.value(ms(100)), -75);
+        assert!(glide.is_finished(ms(200)));
+    }
+
+    #[test]
+    fn indeterminate_segment_sweeps_the_bar() {
+        assert_eq!(indeterminate_segment(400, ms(0)), (0, 0));
+        assert_eq!(indeterminate_segment(400, ms(750)), (150, 250));
+        assert_eq!(indeterminate_segment(400, ms(1400)), (367, 400));
+        // loops
+        assert_eq!(indeterminate_segment(400, ms(2250)), (150, 250));
+    }
+
+    #[test]
+    fn slides_in_from_the_nearest_edge() {
+        let work_area = RECT {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1040,
+        };
+        let right = RECT {
+            left: 1545,
+            top: 855,
+            right: 1905,
+            bottom: 1025,
+        };
+        let left = RECT {
+            left: 15,
+            top: 855,
+            right: 375,
+            bottom: 1025,
+        };
+        assert_eq!(slide_in_origin(right, work_area), (1920, 855));
+        assert_eq!(slide_in_origin(left, work_area), (-360, 855));
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::time::{Duration, Instant};
+
+/// How many notifications arriving together are collapsed into one.
+pub(crate) const BURST_MIN_COUNT: usize = 3;
+/// The longest delay between two notifications of the same burst.
+pub(crate) const BURST_GAP: Duration = Duration::from_secs(2);
+
+/// A shown notification, as seen by the collapsing rules.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Arrival<K> {
+    /// Only notifications with the same key are collapsed together, e.g. the ones of an app.
+    pub(crate) key: K,
+    pub(crate) at: Instant,
+    /// Set once the user expanded the burst of the notification.
+    pub(crate) expanded: bool,
+    /// Whether the notification was hidden in a collapsed burst, it stays out of view.
+    pub(crate) hidden: bool,
+}
+
+/// How a notification is shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Collapse {
+    Shown,
+    /// The latest notification of a collapsed burst, standing for `more` hidden ones.
+    Summary {
+        more: usize,
+    },
+    Hidden,
+}
+
+/// Returns the burst of each arrival, given from the oldest to the newest.
+///
+/// A notification joins the burst of the previous one with the same key when it arrives
+/// at most `gap` after it. Bursts are numbered from `0` in the order they start.
+pub(crate) fn bursts<K: PartialEq>(arrivals: &[Arrival<K>], gap: Duration) -> Vec<usize> {
+    let mut bursts: Vec<usize> = Vec::with_capacity(arrivals.len());
+    let mut count = 0;
+    for (i, arrival) in arrivals.iter().enumerate() {
+        let previous = arrivals[..i].iter().rposition(|a| a.key == arrival.key);
+        let burst = match previous {
+            Some(p) if arrival.at.saturating_duration_since(arrivals[p].at) <= gap => bursts[p],
+            _ => {
+                count += 1;
+                count - 1
+            }
+        };
+        bursts.push(burst);
+    }
+    bursts
+}
+
+/// Returns how each arrival is shown, given from the oldest to the newest.
+///
+/// Bursts of at least `min_count` notifications are collapsed into their latest one,
+/// unless the user expanded them. A collapsed burst stays collapsed when some of its notifications close,
+/// the hidden notifications are only shown again by a newer notification standing for them.
+pub(crate) fn collapse<K: PartialEq>(
+    arrivals: &[Arrival<K>],
+    min_count: usize,
+    gap: Duration,
+) -> Vec<Collapse> {
+    let bursts = bursts(arrivals, gap);
+    bursts
+        .iter()
+        .enumerate()
+        .map(|(i, burst)| {
+            let members = (0..arrivals.len())
+                .filter(|j| bursts[*j] == *burst)
+                .collect::<Vec<_>>();
+            let collapsed =
+                members.len() >= min_count.max(2) || members.iter().any(|j| arrivals[*j].hidden);
+            if !collapsed || members.iter().any(|j| arrivals[*j].expanded) {
+                Collapse::Shown
+            } else if members.last() == Some(&i) && !arrivals[i].hidden {
+                Collapse::Summary {
+                    more: members.len() - 1,
+                }
+            } else {
+                Collapse::Hidden
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Arrivals of the given apps, `(key, seconds after the first one)`.
+    fn arrivals(keys: &[(&'static str, u64)]) -> Vec<Arrival<&'static str>> {
+        let start = Instant::now();
+        keys.iter()
+            .map(|(key, secs)| Arrival {
+                key: *key,
+                at: start + Duration::from_secs(*secs),
+                expanded: false,
+                hidden: false,
+            })
+            .collect()
+    }
+
+    fn collapse(arrivals: &[Arrival<&str>]) -> Vec<Collapse> {
+        super::collapse(arrivals, 3, Duration::from_secs(2))
+    }
+
+    #[test]
+    fn few_notifications_are_shown() {
+        let arrivals = arrivals(&[("mail", 0), ("mail", 1)]);
+        assert_eq!(collapse(&arrivals), [Collapse::Shown, Collapse::Shown]);
+        assert!(collapse(&[]).is_empty());
+    }
+
+    #[test]
+    fn burst_is_collapsed_into_the_latest() {
+        let arrivals = arrivals(&[("mail", 0), ("mail", 1), ("mail", 2)]);
+        assert_eq!(
+            collapse(&arrivals),
+            [
+                Collapse::Hidden,
+                Collapse::Hidden,
+                Collapse::Summary { more: 2 }
+            ]
+        );
+    }
+
+    #[test]
+    fn apps_are_collapsed_separately() {
+        let arrivals = arrivals(&[("mail", 0), ("chat", 0), ("mail", 1), ("mail", 1)]);
+        assert_eq!(bursts(&arrivals, Duration::from_secs(2)), [0, 1, 0, 0]);
+        assert_eq!(
+            collapse(&arrivals),
+            [
+                Collapse::Hidden,
+                Collapse::Shown,
+                Collapse::Hidden,
+                Collapse::Summary { more: 2 }
+            ]
+        );
+    }
+
+    #[test]
+    fn pause_starts_a_new_burst() {
+        let arrivals = arrivals(&[("mail", 0), ("mail", 2), ("mail", 5), ("mail", 6)]);
+        assert_eq!(bursts(&arrivals, Duration::from_secs(2)), [0, 0, 1, 1]);
+        assert!(collapse(&arrivals).iter().all(|c| *c == Collapse::Shown));
+    }
+
+    #[test]
+    fn burst_stays_collapsed_without_its_summary() {
+        let mut arrivals = arrivals(&[("mail", 0), ("mail", 1), ("mail", 2), ("mail", 3)]);
+        for arrival in &mut arrivals[..3] {
+            arrival.hidden = true;
+        }
+        // the summary closed, the others stay out of view
+        assert!(collapse(&arrivals[..3])
+            .iter()
+            .all(|c| *c == Collapse::Hidden));
+        // a newer notification stands for them
+        assert_eq!(
+            collapse(&arrivals[1..]),
+            [
+                Collapse::Hidden,
+                Collapse::Hidden,
+                Collapse::Summary { more: 2 }
+            ]
+        );
+    }
+
+    #[test]
+    fn expanded_burst_is_shown() {
+        let mut arrivals = arrivals(&[("mail", 0), ("mail", 1), ("mail", 2)]);
+        arrivals[2].expanded = true;
+        assert!(collapse(&arrivals).iter().all(|c| *c == Collapse::Shown));
+    }
+}
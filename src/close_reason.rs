@@ -0,0 +1,90 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// Describes why a notification was closed.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The user clicked the body of the notification.
+    Clicked,
+
+    /// The user clicked the close button of the notification.
+    Dismissed,
+
+    /// The notification expired according to its [`Timeout`](crate::Timeout).
+    TimedOut,
+
+    /// The notification was closed by the application.
+    Closed,
+
+    /// The user clicked one of the [`Action`](crate::Action)s of the notification.
+    ActionInvoked,
+
+    /// The user submitted a reply, see [`Notification::reply_input`](crate::Notification::reply_input).
+    Replied,
+
+    /// The notification wasn't shown because of Do Not Disturb or quiet hours,
+    /// see [`NotificationManager::set_quiet_mode`](crate::NotificationManager::set_quiet_mode).
+    Suppressed,
+
+    /// The notification was replaced by one with the same [`Notification::tag`](crate::Notification::tag),
+    /// which has its own `on_close` callback.
+    Replaced,
+}
+
+impl CloseReason {
+    /// Encodes the reason so it can travel in the `WPARAM` of a `WM_CLOSE` message.
+    pub(crate) fn to_wparam(self) -> usize {
+        match self {
+            CloseReason::Clicked => 1,
+            CloseReason::Dismissed => 2,
+            CloseReason::TimedOut => 3,
+            CloseReason::Closed => 4,
+            CloseReason::ActionInvoked => 5,
+            CloseReason::Replied => 6,
+            CloseReason::Suppressed => 7,
+            CloseReason::Replaced => 8,
+        }
+    }
+
+    /// Decodes a reason encoded with [`CloseReason::to_wparam`].
+    ///
+    /// A `WM_CLOSE` sent by the system has a `WPARAM` of `0` and decodes to `None`.
+    pub(crate) fn from_wparam(wparam: usize) -> Option<CloseReason> {
+        match wparam {
+            1 => Some(CloseReason::Clicked),
+            2 => Some(CloseReason::Dismissed),
+            3 => Some(CloseReason::TimedOut),
+            4 => Some(CloseReason::Closed),
+            5 => Some(CloseReason::ActionInvoked),
+            6 => Some(CloseReason::Replied),
+            7 => Some(CloseReason::Suppressed),
+            8 => Some(CloseReason::Replaced),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wparam_round_trip() {
+        let reasons = [
+            CloseReason::Clicked,
+            CloseReason::Dismissed,
+            CloseReason::TimedOut,
+            CloseReason::Closed,
+            CloseReason::ActionInvoked,
+            CloseReason::Replied,
+            CloseReason::Suppressed,
+            CloseReason::Replaced,
+        ];
+        for reason in reasons {
+            assert_eq!(CloseReason::from_wparam(reason.to_wparam()), Some(reason));
+        }
+        assert_eq!(CloseReason::from_wparam(0), None);
+    }
+}
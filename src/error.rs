@@ -0,0 +1,67 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::fmt;
+
+/// Errors returned by this crate.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Registering the notification window class failed, contains the value of `GetLastError`.
+    RegisterClass(u32),
+
+    /// Creating the notification window failed, contains the value of `GetLastError`.
+    CreateWindow(u32),
+
+    /// The icon buffer doesn't describe a `width` x `height` 32bpp RGBA image.
+    InvalidIcon {
+        /// The length of the rgba buffer.
+        len: usize,
+        /// The width of the icon.
+        width: u32,
+        /// The height of the icon.
+        height: u32,
+    },
+
+    /// Initializing COM or the taskbar COM object failed, contains the failing `HRESULT`.
+    ComInit(i32),
+
+    /// Querying the monitor to place the notification on failed.
+    MonitorInfo,
+
+    /// The image buffer doesn't describe a `width` x `height` 32bpp RGBA image.
+    InvalidImage {
+        /// The length of the rgba buffer.
+        len: usize,
+        /// The width of the image.
+        width: u32,
+        /// The height of the image.
+        height: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RegisterClass(code) => {
+                write!(f, "failed to register the window class (error {})", code)
+            }
+            Error::CreateWindow(code) => write!(f, "failed to create the window (error {})", code),
+            Error::InvalidIcon { len, width, height } => write!(
+                f,
+                "invalid icon buffer: {} bytes can't hold a {}x{} 32bpp RGBA image",
+                len, width, height
+            ),
+            Error::ComInit(hr) => write!(f, "failed to initialize COM (HRESULT {:#010x})", hr),
+            Error::MonitorInfo => f.write_str("failed to query the monitor information"),
+            Error::InvalidImage { len, width, height } => write!(
+                f,
+                "invalid image buffer: {} bytes can't hold a {}x{} 32bpp RGBA image",
+                len, width, height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,205 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use windows_sys::Win32::{
+    Foundation::HWND,
+    UI::WindowsAndMessaging::{IsWindowVisible, PostMessageW, WM_CLOSE},
+};
+
+use crate::{
+    close_reason::CloseReason,
+    notification::{self, Notification, WM_REPLACE_NOTIFICATION, WM_UPDATE_NOTIFICATION},
+};
+
+/// A handle to a notification returned by [`Notification::show`].
+///
+/// The handle can be sent to other threads, requests are posted to the thread
+/// that showed the notification and are handled by its event loop.
+#[derive(Debug, Clone)]
+pub struct NotificationHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: Mutex<State>,
+    closed: Condvar,
+}
+
+#[derive(Debug)]
+struct State {
+    /// The notification window, `0` until it is created.
+    hwnd: HWND,
+    /// Set once the notification window is destroyed.
+    reason: Option<CloseReason>,
+    /// Content waiting to be applied by the notification window.
+    update: Option<Notification>,
+    /// A notification with the same tag waiting to replace this one, see [`NotificationHandle::replace`].
+    replacement: Option<Notification>,
+    /// The id of the action clicked by the user.
+    action: Option<String>,
+    /// The text submitted in the reply input.
+    reply: Option<String>,
+}
+
+impl NotificationHandle {
+    pub(crate) fn new() -> NotificationHandle {
+        NotificationHandle {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    hwnd: 0,
+                    reason: None,
+                    update: None,
+                    replacement: None,
+                    action: None,
+                    reply: None,
+                }),
+                closed: Condvar::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the window of the notification if it is still alive.
+    ///
+    /// The window can't be destroyed while the returned guard is held,
+    /// because the window marks the handle as closed on `WM_DESTROY`.
+    fn live_window(&self) -> Option<(HWND, MutexGuard<'_, State>)> {
+        let state = self.state();
+        if state.hwnd != 0 && state.reason.is_none() {
+            Some((state.hwnd, state))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the notification waits in the queue, see
+    /// [`NotificationManager::set_max_visible`](crate::NotificationManager::set_max_visible).
+    fn is_queued(&self) -> bool {
+        let state = self.state();
+        state.hwnd == 0 && state.reason.is_none()
+    }
+
+    /// Closes the notification, its callbacks are called with [`CloseReason::Closed`].
+    ///
+    /// A queued notification is removed from the queue without being shown.
+    /// Does nothing if the notification is already closed.
+    pub fn close(&self) {
+        if let Some((hwnd, _state)) = self.live_window() {
+            unsafe { PostMessageW(hwnd, WM_CLOSE, CloseReason::Closed.to_wparam(), 0) };
+        } else if !self.is_queued() {
+            return;
+        }
+        // along with a replacement held back for the quiet time
+        notification::cancel_queued(self);
+    }
+
+    /// Returns whether the notification is still shown on the screen.
+    pub fn is_visible(&self) -> bool {
+        match self.live_window() {
+            Some((hwnd, _state)) => unsafe { IsWindowVisible(hwnd) != 0 },
+            None => false,
+        }
+    }
+
+    /// Replaces the `appname`, `summary`, `body`, `icon`, `progress`, `image` and app logo of the
+    /// notification with the ones of `notification` and redraws it in place.
+    ///
+    /// A queued notification is shown with the new content.
+    /// Does nothing if the notification is already closed.
+    pub fn update(&self, notification: &Notification) {
+        if let Some((hwnd, mut state)) = self.live_window() {
+            state.update = Some(notification.clone());
+            unsafe { PostMessageW(hwnd, WM_UPDATE_NOTIFICATION, 0, 0) };
+        } else if !self.is_queued() {
+            return;
+        }
+        // along with a replacement held back for the quiet time
+        notification::update_queued(self, notification);
+    }
+
+    /// Replaces the whole notification with `notification`, see [`Notification::tag`].
+    ///
+    /// Returns `false` if the notification is already closed.
+    pub(crate) fn replace(&self, notification: &Notification) -> bool {
+        match self.live_window() {
+            Some((hwnd, mut state)) => {
+                state.replacement = Some(notification.clone());
+                unsafe { PostMessageW(hwnd, WM_REPLACE_NOTIFICATION, 0, 0) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Blocks the current thread until the notification is closed and returns why it was closed.
+    ///
+    /// ## Note
+    ///
+    /// This must not be called from the thread that showed the notification,
+    /// as it would block the event loop the notification relies on and never return.
+    pub fn wait_for_close(&self) -> CloseReason {
+        let mut state = self.state();
+        loop {
+            if let Some(reason) = state.reason {
+                return reason;
+            }
+            state = self
+                .inner
+                .closed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Returns the id of the [`Action`](crate::Action) clicked by the user, if any.
+    pub fn invoked_action(&self) -> Option<String> {
+        self.state().action.clone()
+    }
+
+    /// Returns the text the user replied with, see [`Notification::reply_input`].
+    pub fn reply(&self) -> Option<String> {
+        self.state().reply.clone()
+    }
+
+    /// Returns whether both handles refer to the same notification.
+    pub(crate) fn same(&self, other: &NotificationHandle) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub(crate) fn set_window(&self, hwnd: HWND) {
+        self.state().hwnd = hwnd;
+    }
+
+    pub(crate) fn set_invoked_action(&self, id: &str) {
+        self.state().action = Some(id.to_owned());
+    }
+
+    pub(crate) fn set_reply(&self, reply: &str) {
+        self.state().reply = Some(reply.to_owned());
+    }
+
+    pub(crate) fn take_update(&self) -> Option<Notification> {
+        self.state().update.take()
+    }
+
+    pub(crate) fn take_replacement(&self) -> Option<Notification> {
+        self.state().replacement.take()
+    }
+
+    pub(crate) fn set_closed(&self, reason: CloseReason) {
+        let mut state = self.state();
+        state.reason = Some(reason);
+        state.update = None;
+        state.replacement = None;
+        self.inner.closed.notify_all();
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::error::Error;
+
+/// Size in bytes of a 32bpp RGBA pixel.
+pub(crate) const PIXEL_SIZE: usize = 4;
+
+/// Returns whether a buffer of `len` bytes holds a `width` x `height` 32bpp RGBA image.
+pub(crate) fn is_rgba(len: usize, width: u32, height: u32) -> bool {
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(PIXEL_SIZE));
+    len % PIXEL_SIZE == 0 && expected == Some(len)
+}
+
+/// A 32bpp RGBA image, see [`Notification::image`](crate::Notification::image).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl Image {
+    /// Returns [`Error::InvalidImage`] if the length of `rgba` is not divisible by 4
+    /// or `width * height` is not equal to `rgba.len() / 4`.
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Image, Error> {
+        if !is_rgba(rgba.len(), width, height) {
+            return Err(Error::InvalidImage {
+                len: rgba.len(),
+                width,
+                height,
+            });
+        }
+        Ok(Image {
+            rgba,
+            width,
+            height,
+        })
+    }
+
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Crops the center of the image to the aspect ratio of `width` x `height` and resizes it to that size.
+    pub(crate) fn resize_to_fill(&self, width: u32, height: u32) -> Image {
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return Image::transparent(width, height);
+        }
+
+        // the largest centered region with the target aspect ratio
+        let (w, h) = (self.width as u64, self.height as u64);
+        let (crop_width, crop_height) = if w * height as u64 > h * width as u64 {
+            (
+                (h * width as u64 / height as u64).max(1) as u32,
+                self.height,
+            )
+        } else {
+            (self.width, (w * height as u64 / width as u64).max(1) as u32)
+        };
+        let x = ((self.width - crop_width) / 2) as usize;
+        let y = ((self.height - crop_height) / 2) as usize;
+        let row_len = crop_width as usize * PIXEL_SIZE;
+        let cropped = self
+            .rgba
+            .chunks_exact(self.width as usize * PIXEL_SIZE)
+            .skip(y)
+            .take(crop_height as usize)
+            .flat_map(|row| {
+                row[x * PIXEL_SIZE..x * PIXEL_SIZE + row_len]
+                    .iter()
+                    .copied()
+            })
+            .collect();
+
+        Image {
+            rgba: cropped,
+            width: crop_width,
+            height: crop_height,
+        }
+        .resize(width, height)
+    }
+
+    /// Resizes the image with a triangle filter, widened when shrinking so every source pixel
+    /// contributes to the result instead of being skipped.
+    pub(crate) fn resize(&self, width: u32, height: u32) -> Image {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return Image::transparent(width, height);
+        }
+
+        // premultiplied, so the color of transparent pixels doesn't bleed into their neighbors
+        let pixels: Vec<[f32; 4]> = self
+            .rgba
+            .chunks_exact(PIXEL_SIZE)
+            .map(|p| {
+                let alpha = p[3] as f32 / 255.0;
+                [
+                    p[0] as f32 * alpha,
+                    p[1] as f32 * alpha,
+                    p[2] as f32 * alpha,
+                    p[3] as f32,
+                ]
+            })
+            .collect();
+
+        let columns = filter_weights(self.width, width);
+        let horizontal: Vec<[f32; 4]> = pixels
+            .chunks_exact(self.width as usize)
+            .flat_map(|row| {
+                columns
+                    .iter()
+                    .map(move |(start, w)| apply(w, |k| row[start + k]))
+            })
+            .collect();
+
+        let rows = filter_weights(self.height, height);
+        let new_width = width as usize;
+        let rgba = rows
+            .iter()
+            .flat_map(|(start, w)| {
+                let horizontal = &horizontal;
+                (0..new_width).map(move |x| apply(w, |k| horizontal[(start + k) * new_width + x]))
+            })
+            .flat_map(|[r, g, b, a]| {
+                let unpremultiply = |c: f32| {
+                    if a > 0.0 {
+                        (c * 255.0 / a).round().clamp(0.0, 255.0) as u8
+                    } else {
+                        0
+                    }
+                };
+                [
+                    unpremultiply(r),
+                    unpremultiply(g),
+                    unpremultiply(b),
+                    a.round().clamp(0.0, 255.0) as u8,
+                ]
+            })
+            .collect();
+
+        Image {
+            rgba,
+            width,
+            height,
+        }
+    }
+
+    /// Makes the pixels outside of the circle inscribed in the image transparent, with smooth edges.
+    pub(crate) fn mask_circle(&mut self) {
+        let (cx, cy) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+        let radius = cx.min(cy);
+        let width = self.width as usize;
+        for (i, pixel) in self.rgba.chunks_exact_mut(PIXEL_SIZE).enumerate() {
+            let x = (i % width) as f32 + 0.5 - cx;
+            let y = (i / width) as f32 + 0.5 - cy;
+            let coverage = (radius - (x * x + y * y).sqrt() + 0.5).clamp(0.0, 1.0);
+            pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+        }
+    }
+
+    fn transparent(width: u32, height: u32) -> Image {
+        Image {
+            rgba: vec![0; width as usize * height as usize * PIXEL_SIZE],
+            width,
+            height,
+        }
+    }
+}
+
+/// The shape of the app logo, see [`Notification::app_logo_override`](crate::Notification::app_logo_override).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogoCrop {
+    Square,
+    Circle,
+}
+
+impl Default for LogoCrop {
+    fn default() -> Self {
+        LogoCrop::Square
+    }
+}
+
+/// Returns, for each of the `dst_len` destination pixels of a row or column, the index of the first
+/// source pixel it is computed from and the normalized weights of the source pixels.
+fn filter_weights(src_len: u32, dst_len: u32) -> Vec<(usize, Vec<f32>)> {
+    let scale = src_len as f32 / dst_len as f32;
+    let support = scale.max(1.0);
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let start = (center - support).floor().max(0.0) as usize;
+            let end = ((center + support).ceil() as usize).min(src_len as usize);
+            let mut weights: Vec<f32> = (start..end)
+                .map(|j| (1.0 - ((j as f32 + 0.5 - center) / support).abs()).max(0.0))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            if sum > 0.0 {
+                weights.iter_mut().for_each(|w| *w /= sum);
+            }
+            (start, weights)
+        })
+        .collect()
+}
+
+/// Sums the pixels returned by `pixel` for each weight index, multiplied by the weights.
+fn apply(weights: &[f32], pixel: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+    for (k, w) in weights.iter().enumerate() {
+        let p = pixel(k);
+        for (s, c) in sum.iter_mut().zip(p) {
+            *s += c * w;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, pixel: [u8; 4]) -> Image {
+        let rgba = pixel
+            .iter()
+            .copied()
+            .cycle()
+            .take(width as usize * height as usize * PIXEL_SIZE)
+            .collect();
+        Image::from_rgba(rgba, width, height).unwrap()
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let i = (y * image.width + x) as usize * PIXEL_SIZE;
+        let p = &image.rgba[i..i + PIXEL_SIZE];
+        [p[0], p[1], p[2], p[3]]
+    }
+
+    #[test]
+    fn validates_the_buffer() {
+        assert!(is_rgba(16, 2, 2));
+        assert!(!is_rgba(15, 2, 2));
+        assert!(!is_rgba(16, 4, 4));
+        assert!(!is_rgba(16, u32::MAX, u32::MAX));
+        assert_eq!(
+            Image::from_rgba(vec![0; 3], 1, 1),
+            Err(Error::InvalidImage {
+                len: 3,
+                width: 1,
+                height: 1
+            })
+        );
+    }
+
+    #[test]
+    fn resizing_keeps_solid_colors() {
+        let image = solid(64, 32, [10, 20, 30, 255]);
+        for (w, h) in [(16, 16), (100, 50), (1, 1)] {
+            let resized = image.resize(w, h);
+            assert_eq!((resized.width, resized.height), (w, h));
+            assert!(resized
+                .rgba
+                .chunks_exact(PIXEL_SIZE)
+                .all(|p| p == [10, 20, 30, 255]));
+        }
+    }
+
+    #[test]
+    fn shrinking_averages_all_pixels() {
+        // black and white stripes turn gray instead of keeping one of the colors
+        let rgba = (0..8)
+            .flat_map(|x| {
+                let c = if x % 2 == 0 { 0 } else { 255 };
+                [c, c, c, 255]
+            })
+            .collect();
+        let image = Image::from_rgba(rgba, 8, 1).unwrap();
+        let resized = image.resize(2, 1);
+        for x in 0..2 {
+            let [r, _, _, a] = pixel(&resized, x, 0);
+            assert!((112..=143).contains(&r), "{}", r);
+            assert_eq!(a, 255);
+        }
+    }
+
+    #[test]
+    fn transparent_pixels_do_not_bleed() {
+        let mut rgba = vec![0; 4 * PIXEL_SIZE];
+        rgba[..4].copy_from_slice(&[255, 0, 0, 255]);
+        let image = Image::from_rgba(rgba, 4, 1).unwrap();
+        let resized = image.resize(2, 1);
+        let [r, g, b, a] = pixel(&resized, 0, 0);
+        assert_eq!((r, g, b), (255, 0, 0));
+        assert!(a > 0 && a < 255);
+    }
+
+    #[test]
+    fn fill_crops_the_center() {
+        // left half red, right half blue, 4:1
+        let rgba = (0..8 * 2)
+            .flat_map(|i| {
+                if i % 8 < 4 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect();
+        let image = Image::from_rgba(rgba, 8, 2).unwrap();
+        let filled = image.resize_to_fill(2, 2);
+        assert_eq!((filled.width, filled.height), (2, 2));
+        assert_eq!(pixel(&filled, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&filled, 1, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn circle_mask() {
+        let mut image = solid(16, 16, [0, 0, 0, 255]);
+        image.mask_circle();
+        assert_eq!(pixel(&image, 0, 0)[3], 0);
+        assert_eq!(pixel(&image, 15, 15)[3], 0);
+        assert_eq!(pixel(&image, 8, 8)[3], 255);
+        assert_eq!(pixel(&image, 1, 8)[3], 255);
+        let edge = pixel(&image, 2, 2)[3];
+        assert!(edge > 0 && edge < 255, "{}", edge);
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use windows_sys::Win32::Foundation::RECT;
+
+/// Gap between the edges of the work area and the notifications.
+pub(crate) const EDGE_GAP: i32 = 15;
+/// Gap between two stacked notifications.
+pub(crate) const STACK_GAP: i32 = 10;
+
+/// The corner of the screen notifications are shown in,
+/// see [`NotificationManager::set_position`](crate::NotificationManager::set_position).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// The direction notifications are stacked in, away from their [`Corner`],
+/// see [`NotificationManager::set_stack_direction`](crate::NotificationManager::set_stack_direction).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StackDirection {
+    /// Older notifications are moved above or below the newest one.
+    Vertical,
+    /// Older notifications are moved to the left or right of the newest one.
+    Horizontal,
+}
+
+impl Default for StackDirection {
+    fn default() -> Self {
+        StackDirection::Vertical
+    }
+}
+
+impl Corner {
+    /// Returns the corner next to the notification area of the taskbar.
+    ///
+    /// The taskbar edge is deduced from the part of the monitor that is not in the work area,
+    /// notifications go to the bottom right corner when the taskbar is hidden.
+    pub(crate) fn from_taskbar(work_area: RECT, monitor: RECT) -> Corner {
+        if work_area.top > monitor.top {
+            Corner::TopRight
+        } else if work_area.left > monitor.left {
+            Corner::BottomLeft
+        } else {
+            Corner::BottomRight
+        }
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+}
+
+/// Computes the rects of stacked notifications.
+///
+/// `sizes` are the `(width, height)` of the notifications ordered from the newest to the oldest.
+/// The newest notification is placed in `anchor`, or next to the taskbar if `None`,
+/// and the older ones are stacked away from it in `direction`.
+pub(crate) fn layout(
+    work_area: RECT,
+    monitor: RECT,
+    anchor: Option<Corner>,
+    direction: StackDirection,
+    sizes: &[(i32, i32)],
+) -> Vec<RECT> {
+    let corner = anchor.unwrap_or_else(|| Corner::from_taskbar(work_area, monitor));
+
+    let mut offset = EDGE_GAP;
+    sizes
+        .iter()
+        .map(|&(width, height)| {
+            let (x_offset, y_offset) = match direction {
+                StackDirection::Vertical => (EDGE_GAP, offset),
+                StackDirection::Horizontal => (offset, EDGE_GAP),
+            };
+            let left = if corner.is_left() {
+                work_area.left + x_offset
+            } else {
+                work_area.right - x_offset - width
+            };
+            let top = if corner.is_top() {
+                work_area.top + y_offset
+            } else {
+                work_area.bottom - y_offset - height
+            };
+            offset += STACK_GAP
+                + match direction {
+                    StackDirection::Vertical => height,
+                    StackDirection::Horizontal => width,
+                };
+
+            RECT {
+                left,
+                top,
+                right: left + width,
+                bottom: top + height,
+            }
+        })
+        .collect()
+}
+
+/// Returns whether notifications of the given `(width, height)` stacked in `direction` fit in the work area.
+pub(crate) fn fits(work_area: RECT, direction: StackDirection, sizes: &[(i32, i32)]) -> bool {
+    let (available, extents): (i32, Vec<i32>) = match direction {
+        StackDirection::Vertical => (
+            work_area.bottom - work_area.top,
+            sizes.iter().map(|&(_, height)| height).collect(),
+        ),
+        StackDirection::Horizontal => (
+            work_area.right - work_area.left,
+            sizes.iter().map(|&(width, _)| width).collect(),
+        ),
+    };
+    let gaps = STACK_GAP * (extents.len() as i32 - 1).max(0);
+    EDGE_GAP * 2 + extents.iter().sum::<i32>() + gaps <= available
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> RECT {
+        RECT {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    fn assert_rects(actual: Vec<RECT>, expected: &[(i32, i32, i32, i32)]) {
+        let actual: Vec<_> = actual
+            .iter()
+            .map(|r| (r.left, r.top, r.right, r.bottom))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    const MONITOR: RECT = RECT {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1080,
+    };
+
+    #[test]
+    fn taskbar_corner() {
+        let bottom = rect(0, 0, 1920, 1040);
+        let top = rect(0, 40, 1920, 1080);
+        let left = rect(60, 0, 1920, 1080);
+        let right = rect(0, 0, 1860, 1080);
+        assert_eq!(Corner::from_taskbar(bottom, MONITOR), Corner::BottomRight);
+        assert_eq!(Corner::from_taskbar(top, MONITOR), Corner::TopRight);
+        assert_eq!(Corner::from_taskbar(left, MONITOR), Corner::BottomLeft);
+        assert_eq!(Corner::from_taskbar(right, MONITOR), Corner::BottomRight);
+        assert_eq!(Corner::from_taskbar(MONITOR, MONITOR), Corner::BottomRight);
+    }
+
+    #[test]
+    fn stacks_upwards_above_bottom_taskbar() {
+        let work_area = rect(0, 0, 1920, 1040);
+        let rects = layout(
+            work_area,
+            MONITOR,
+            None,
+            StackDirection::Vertical,
+            &[(360, 170), (360, 218)],
+        );
+        assert_rects(rects, &[(1545, 855, 1905, 1025), (1545, 627, 1905, 845)]);
+    }
+
+    #[test]
+    fn stacks_downwards_below_top_taskbar() {
+        let work_area = rect(0, 40, 1920, 1080);
+        let rects = layout(
+            work_area,
+            MONITOR,
+            None,
+            StackDirection::Vertical,
+            &[(360, 170), (360, 170)],
+        );
+        assert_rects(rects, &[(1545, 55, 1905, 225), (1545, 235, 1905, 405)]);
+    }
+
+    #[test]
+    fn stays_right_of_left_taskbar() {
+        let work_area = rect(60, 0, 1920, 1080);
+        let rects = layout(
+            work_area,
+            MONITOR,
+            None,
+            StackDirection::Vertical,
+            &[(360, 170)],
+        );
+        assert_rects(rects, &[(75, 895, 435, 1065)]);
+    }
+
+    #[test]
+    fn explicit_anchor_overrides_taskbar() {
+        let work_area = rect(0, 0, 1920, 1040);
+        let rects = layout(
+            work_area,
+            MONITOR,
+            Some(Corner::TopLeft),
+            StackDirection::Vertical,
+            &[(360, 170)],
+        );
+        assert_rects(rects, &[(15, 15, 375, 185)]);
+    }
+
+    #[test]
+    fn secondary_monitor_coordinates() {
+        let monitor = rect(-1280, 0, 0, 1024);
+        let work_area = rect(-1280, 0, 0, 984);
+        let rects = layout(
+            work_area,
+            monitor,
+            None,
+            StackDirection::Vertical,
+            &[(360, 170)],
+        );
+        assert_rects(rects, &[(-375, 799, -15, 969)]);
+    }
+
+    #[test]
+    fn empty_stack() {
+        assert!(layout(MONITOR, MONITOR, None, StackDirection::Vertical, &[]).is_empty());
+    }
+
+    #[test]
+    fn stacks_horizontally() {
+        let work_area = rect(0, 0, 1920, 1040);
+        let rects = layout(
+            work_area,
+            MONITOR,
+            Some(Corner::BottomRight),
+            StackDirection::Horizontal,
+            &[(360, 170), (360, 218)],
+        );
+        assert_rects(rects, &[(1545, 855, 1905, 1025), (1175, 807, 1535, 1025)]);
+
+        let rects = layout(
+            work_area,
+            MONITOR,
+            Some(Corner::TopLeft),
+            StackDirection::Horizontal,
+            &[(360, 170), (360, 170)],
+        );
+        assert_rects(rects, &[(15, 15, 375, 185), (385, 15, 745, 185)]);
+    }
+
+    #[test]
+    fn stack_fits_in_the_work_area() {
+        let work_area = rect(0, 0, 1920, 1040);
+        assert!(fits(work_area, StackDirection::Vertical, &[(360, 170); 5]));
+        assert!(!fits(work_area, StackDirection::Vertical, &[(360, 170); 6]));
+        assert!(fits(
+            work_area,
+            StackDirection::Horizontal,
+            &[(360, 170); 5]
+        ));
+        assert!(!fits(
+            work_area,
+            StackDirection::Horizontal,
+            &[(360, 170); 6]
+        ));
+        assert!(fits(work_area, StackDirection::Vertical, &[]));
+    }
+}
@@ -14,6 +14,7 @@
 //! # Example 1: Simple Notification
 //!
 //! ```no_run
+//! # #[cfg(windows)] {
 //! # use win7_notifications::*;
 //! # let icon = &[];
 //! Notification::new()
@@ -23,11 +24,13 @@
 //!     .icon(icon.to_vec(), 32, 32)
 //!     .timeout(Timeout::Default) // 5000 milliseconds
 //!     .show().unwrap();
+//! # }
 //! ```
 //!
 //! # Example 2: Presistent Notification
 //!
 //! ```no_run
+//! # #[cfg(windows)] {
 //! # use win7_notifications::*;
 //! # let icon = &[];
 //! Notification::new()
@@ -37,12 +40,184 @@
 //!     .icon(icon.to_vec(), 32, 32)
 //!     .timeout(Timeout::Never)
 //!     .show().unwrap();
+//! # }
 //! ```
 //!
+//! # Example 3: Callbacks
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! Notification::new()
+//!     .summary("New message")
+//!     .body("Click to open the conversation.")
+//!     .on_click(|| println!("clicked"))
+//!     .on_close(|reason| println!("closed: {:?}", reason))
+//!     .show().unwrap();
+//! # }
+//! ```
+//!
+//! # Example 4: Updating a Notification
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! let handle = Notification::new()
+//!     .summary("Downloading")
+//!     .body("Starting...")
+//!     .timeout(Timeout::Never)
+//!     .show().unwrap();
+//!
+//! handle.update(Notification::new().summary("Downloading").body("Almost done..."));
+//! handle.close();
+//! # }
+//! ```
+//!
+//! # Example 5: Progress
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! let mut notification = Notification::new();
+//! notification
+//!     .summary("Syncing")
+//!     .timeout(Timeout::Never)
+//!     .progress(Progress::indeterminate().status("Preparing..."));
+//! let handle = notification.show().unwrap();
+//!
+//! for i in 1..=10 {
+//!     let progress = Progress::new(i as f64 / 10.0)
+//!         .status("Uploading files")
+//!         .label(&format!("{} of 10", i));
+//!     handle.update(notification.progress(progress));
+//! }
+//! # }
+//! ```
+//!
+//! # Example 6: Theming
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! let mut theme = Theme::light();
+//! theme.corner_radius = 8;
+//! NotificationManager::set_theme(theme);
+//!
+//! Notification::new()
+//!     .summary("Themed")
+//!     .body("This notification uses the light theme.")
+//!     .show().unwrap();
+//! # }
+//! ```
+//!
+//! # Example 7: Images
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! # let (photo, avatar) = (&[], &[]);
+//! Notification::new()
+//!     .summary("New photo")
+//!     .body("Alice shared a photo with you.")
+//!     .image(photo.to_vec(), 1280, 720)
+//!     .app_logo_override(avatar.to_vec(), 96, 96, LogoCrop::Circle)
+//!     .show().unwrap();
+//! # }
+//! ```
+//!
+//! # Example 8: Replying
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! Notification::new()
+//!     .summary("Alice")
+//!     .body("Are you coming tonight?")
+//!     .reply_input("Type a reply")
+//!     .on_reply(|text| println!("replied: {}", text))
+//!     .show().unwrap();
+//! # }
+//! ```
+//!
+//! # Example 9: Replacing Notifications
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! // the second notification replaces the first one in place
+//! for count in 1..=2 {
+//!     Notification::new()
+//!         .summary(&format!("{} unread messages", count))
+//!         .tag("unread")
+//!         .group("chat")
+//!         .silent_replace(true)
+//!         .show().unwrap();
+//! }
+//!
+//! NotificationManager::remove_group("chat");
+//! # }
+//! ```
+//!
+//! # Example 10: Quiet Hours
+//!
+//! ```no_run
+//! # #[cfg(windows)] {
+//! # use win7_notifications::*;
+//! // hold the notifications back at night and during presentations, critical ones still show up
+//! NotificationManager::set_quiet_hours(QuietHours::new((22, 0), (7, 0)));
+//! NotificationManager::set_quiet_when_fullscreen(true);
+//! NotificationManager::set_quiet_mode(QuietMode::CriticalOnly);
+//! # }
+//! ```
+//!
+
+// the window and Win32 parts only build on Windows, the others are tested on any platform
+#![cfg_attr(not(windows), allow(dead_code))]
 
+mod action;
+mod animation;
+mod burst;
+mod close_reason;
+#[cfg(windows)]
 mod definitions;
+mod error;
+#[cfg(windows)]
+mod handle;
+mod image;
+mod layout;
+#[cfg(windows)]
+mod manager;
+mod metrics;
+#[cfg(windows)]
+mod monitor;
+#[cfg(windows)]
 mod notification;
+mod progress;
+mod queue;
+mod quiet;
+mod sound;
+mod text;
+mod theme;
 mod timeout;
+mod urgency;
+#[cfg(windows)]
 mod util;
 
-pub use crate::{notification::Notification, timeout::Timeout};
+pub use crate::{
+    action::Action,
+    close_reason::CloseReason,
+    error::Error,
+    image::{Image, LogoCrop},
+    layout::{Corner, StackDirection},
+    progress::Progress,
+    quiet::{QuietHours, QuietMode},
+    sound::Sound,
+    theme::{Color, Theme, ThemeMode},
+    timeout::Timeout,
+    urgency::Urgency,
+};
+#[cfg(windows)]
+pub use crate::{
+    handle::NotificationHandle, manager::NotificationManager, monitor::MonitorPolicy,
+    notification::Notification,
+};
@@ -0,0 +1,201 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use once_cell::sync::Lazy;
+use std::sync::{Mutex, PoisonError};
+
+use crate::{
+    layout::{Corner, StackDirection},
+    notification,
+    quiet::{QuietHours, QuietMode, QuietSettings},
+    theme::{Theme, ThemeMode},
+    util,
+};
+
+static SETTINGS: Lazy<Mutex<Settings>> = Lazy::new(|| Mutex::new(Settings::default()));
+
+/// Settings shared by all the notifications of the process.
+#[derive(Debug, Clone)]
+pub(crate) struct Settings {
+    /// The corner notifications are shown in, next to the taskbar if `None`.
+    pub(crate) position: Option<Corner>,
+    pub(crate) stack_direction: StackDirection,
+    pub(crate) animations: bool,
+    /// How the theme of the notifications that don't have their own is chosen.
+    pub(crate) theme_mode: ThemeMode,
+    /// Whether [`Urgency::Low`](crate::Urgency::Low) notifications are collapsed to their summary.
+    pub(crate) collapse_low_urgency: bool,
+    /// How many notifications are shown at once on a monitor, as many as fit in its work area if `None`.
+    pub(crate) max_visible: Option<usize>,
+    /// Whether notifications of the same app shown together are collapsed into one.
+    pub(crate) collapse_bursts: bool,
+    /// When notifications are held back and what happens to them meanwhile.
+    pub(crate) quiet: QuietSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            position: None,
+            stack_direction: StackDirection::default(),
+            animations: true,
+            theme_mode: ThemeMode::default(),
+            collapse_low_urgency: false,
+            max_visible: None,
+            collapse_bursts: false,
+            quiet: QuietSettings::default(),
+        }
+    }
+}
+
+/// Returns whether notifications are held back now, according to `quiet`.
+pub(crate) fn is_quiet(quiet: &QuietSettings) -> bool {
+    quiet.is_quiet(&util::LocalClock, util::is_fullscreen_app_running)
+}
+
+/// Returns a snapshot of the current settings.
+pub(crate) fn settings() -> Settings {
+    SETTINGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+fn update_settings(f: impl FnOnce(&mut Settings)) {
+    f(&mut SETTINGS.lock().unwrap_or_else(PoisonError::into_inner));
+}
+
+/// Configures how all the notifications of the process are shown.
+///
+/// Changes apply to new notifications as well as the ones already shown.
+#[derive(Debug)]
+pub struct NotificationManager {
+    _private: (),
+}
+
+impl NotificationManager {
+    /// Set the corner of the screen notifications are shown in.
+    ///
+    /// By default, notifications are shown in the corner next to the notification area of the taskbar.
+    pub fn set_position(corner: Corner) {
+        update_settings(|s| s.position = Some(corner));
+        notification::reposition_active_notifications();
+    }
+
+    /// Show notifications next to the notification area of the taskbar again, which is the default.
+    pub fn reset_position() {
+        update_settings(|s| s.position = None);
+        notification::reposition_active_notifications();
+    }
+
+    /// Set the direction notifications are stacked in, away from their corner.
+    ///
+    /// Defaults to [`StackDirection::Vertical`].
+    pub fn set_stack_direction(direction: StackDirection) {
+        update_settings(|s| s.stack_direction = direction);
+        notification::reposition_active_notifications();
+    }
+
+    /// Set the theme of the notifications, unless they have their own [`Notification::theme`](crate::Notification::theme).
+    ///
+    /// Defaults to [`Theme::dark`], this is a shorthand for `set_theme_mode(ThemeMode::Fixed(theme))`.
+    pub fn set_theme(theme: Theme) {
+        NotificationManager::set_theme_mode(ThemeMode::Fixed(theme));
+    }
+
+    /// Set how the theme of the notifications is chosen, use [`ThemeMode::System`] to follow
+    /// the light or dark mode and the accent color of Windows.
+    pub fn set_theme_mode(mode: ThemeMode) {
+        update_settings(|s| s.theme_mode = mode);
+        notification::refresh_active_themes();
+    }
+
+    /// Enable or disable the slide-in, fade-out and glide animations of the notifications.
+    ///
+    /// Animations are enabled by default, they are also skipped when client area animations
+    /// are turned off in the system settings.
+    pub fn set_animations_enabled(enabled: bool) {
+        update_settings(|s| s.animations = enabled);
+    }
+
+    /// Collapse [`Urgency::Low`](crate::Urgency::Low) notifications to their summary,
+    /// hiding their body and image. Disabled by default.
+    pub fn set_collapse_low_urgency(enabled: bool) {
+        update_settings(|s| s.collapse_low_urgency = enabled);
+        notification::refresh_active_themes();
+    }
+
+    /// Collapse notifications of the same app shown in quick succession into the latest one,
+    /// with a "+N more" line that expands them back into the full stack when clicked. Disabled by default.
+    ///
+    /// The collapsed notifications keep expiring while they are hidden. When the user dismisses or clicks
+    /// the notification standing for them, they are closed with [`CloseReason::Dismissed`](crate::CloseReason::Dismissed).
+    /// When it closes otherwise, e.g. when it expires, they stay collapsed and out of view until they expire
+    /// or a newer notification of the app stands for them.
+    pub fn set_collapse_bursts(enabled: bool) {
+        update_settings(|s| s.collapse_bursts = enabled);
+        notification::reposition_active_notifications();
+    }
+
+    /// Set how many notifications are shown at once on a monitor, at least one.
+    ///
+    /// Notifications shown past this count wait in a queue, the most urgent first,
+    /// and are shown as earlier notifications close. Notifications already shown stay on the screen,
+    /// queued notifications are shown right away if the new count leaves room for them.
+    pub fn set_max_visible(count: usize) {
+        update_settings(|s| s.max_visible = Some(count.max(1)));
+        notification::show_queued();
+    }
+
+    /// Show as many notifications as fit in the work area of the monitor at once, which is the default.
+    pub fn reset_max_visible() {
+        update_settings(|s| s.max_visible = None);
+        notification::show_queued();
+    }
+
+    /// Close the notifications shown or queued with the given [`Notification::group`](crate::Notification::group),
+    /// their callbacks are called with [`CloseReason::Closed`](crate::CloseReason::Closed).
+    pub fn remove_group(group: &str) {
+        notification::remove_group(group);
+    }
+
+    /// Turn Do Not Disturb on or off, notifications are held back while it is on,
+    /// see [`NotificationManager::set_quiet_mode`].
+    pub fn set_do_not_disturb(enabled: bool) {
+        update_settings(|s| s.quiet.do_not_disturb = enabled);
+        notification::show_queued();
+    }
+
+    /// Hold notifications back every day during `hours`, see [`NotificationManager::set_quiet_mode`].
+    pub fn set_quiet_hours(hours: QuietHours) {
+        update_settings(|s| s.quiet.hours = Some(hours));
+        notification::show_queued();
+    }
+
+    /// Remove the quiet hours, which is the default.
+    pub fn reset_quiet_hours() {
+        update_settings(|s| s.quiet.hours = None);
+        notification::show_queued();
+    }
+
+    /// Hold notifications back while a full screen app, a game or a presentation is running. Disabled by default.
+    pub fn set_quiet_when_fullscreen(enabled: bool) {
+        update_settings(|s| s.quiet.when_fullscreen = enabled);
+        notification::show_queued();
+    }
+
+    /// Set what happens to the notifications shown during Do Not Disturb or quiet hours.
+    ///
+    /// Defaults to [`QuietMode::Queue`], notifications already shown stay on the screen.
+    pub fn set_quiet_mode(mode: QuietMode) {
+        update_settings(|s| s.quiet.mode = mode);
+        notification::show_queued();
+    }
+
+    /// Returns whether notifications are held back now,
+    /// because of Do Not Disturb, quiet hours or a full screen app.
+    pub fn is_quiet() -> bool {
+        is_quiet(&settings().quiet)
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use windows_sys::Win32::Foundation::RECT;
+
+use crate::theme::Theme;
+
+/// The DPI the metrics below are designed for, 100% scaling.
+pub(crate) const BASE_DPI: u32 = 96;
+
+/// notification width
+const NW: i32 = 360;
+/// minimum notification height
+const NMINH: i32 = 100;
+/// maximum notification height
+const NMAXH: i32 = 480;
+/// notification icon size (width or height)
+const NIS: i32 = 16;
+/// close button size (width or height)
+const CBS: i32 = 8;
+/// close button pen width
+const CBP: i32 = 2;
+/// action button height
+const ABH: i32 = 32;
+/// gap between action buttons
+const ABG: i32 = 4;
+/// progress bar height
+const PBH: i32 = 4;
+/// app logo size (width or height)
+const LS: i32 = 48;
+/// critical notification stripe width
+const CSW: i32 = 4;
+/// reply input height
+const RIH: i32 = 32;
+
+/// Scales a length designed for [`BASE_DPI`] to `dpi`, rounding to the nearest pixel like `MulDiv`.
+pub(crate) fn scale(value: i32, dpi: u32) -> i32 {
+    let n = value as i64 * dpi as i64;
+    let d = BASE_DPI as i64;
+    let rounded = if n >= 0 {
+        (n + d / 2) / d
+    } else {
+        (n - d / 2) / d
+    };
+    rounded as i32
+}
+
+/// Sizes used to lay out and draw a notification, in physical pixels for a given DPI and [`Theme`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Metrics {
+    pub(crate) dpi: u32,
+    pub(crate) width: i32,
+    /// The height of a notification with little content, the body is stretched to fill it.
+    pub(crate) min_height: i32,
+    /// The height notifications with long text are cut to.
+    pub(crate) max_height: i32,
+    pub(crate) margin: i32,
+    pub(crate) icon_size: i32,
+    pub(crate) close_btn_size: i32,
+    pub(crate) close_btn_pen_width: i32,
+    pub(crate) action_btn_height: i32,
+    pub(crate) action_btn_gap: i32,
+    pub(crate) progress_bar_height: i32,
+    pub(crate) logo_size: i32,
+    pub(crate) critical_stripe_width: i32,
+    pub(crate) reply_input_height: i32,
+    pub(crate) appname_font_size: i32,
+    pub(crate) summary_font_size: i32,
+    pub(crate) body_font_size: i32,
+    pub(crate) action_font_size: i32,
+    pub(crate) corner_radius: i32,
+}
+
+impl Metrics {
+    pub(crate) fn new(dpi: u32, theme: &Theme) -> Metrics {
+        Metrics {
+            dpi,
+            width: scale(NW, dpi),
+            min_height: scale(NMINH, dpi),
+            max_height: scale(NMAXH, dpi),
+            margin: scale(theme.margin, dpi),
+            icon_size: scale(NIS, dpi),
+            close_btn_size: scale(CBS, dpi),
+            close_btn_pen_width: scale(CBP, dpi).max(1),
+            action_btn_height: scale(ABH, dpi),
+            action_btn_gap: scale(ABG, dpi),
+            progress_bar_height: scale(PBH, dpi).max(1),
+            logo_size: scale(LS, dpi),
+            critical_stripe_width: scale(CSW, dpi).max(1),
+            reply_input_height: scale(RIH, dpi),
+            appname_font_size: scale(theme.appname_font_size, dpi),
+            summary_font_size: scale(theme.title_font_size, dpi),
+            body_font_size: scale(theme.body_font_size, dpi),
+            action_font_size: scale(theme.action_font_size, dpi),
+            corner_radius: scale(theme.corner_radius, dpi),
+        }
+    }
+
+    /// Returns the rect the close button cross is drawn in.
+    pub(crate) fn close_btn_rect(&self) -> RECT {
+        let left = self.width - self.margin - self.margin / 2;
+        RECT {
+            left,
+            top: self.margin,
+            right: left + self.close_btn_size,
+            bottom: self.margin + self.close_btn_size,
+        }
+    }
+
+    /// Returns the rect reacting to the mouse around the close button, larger than the cross to be easier to hit.
+    pub(crate) fn close_btn_hit_rect(&self) -> RECT {
+        let rect = self.close_btn_rect();
+        RECT {
+            left: rect.left - self.close_btn_size,
+            top: rect.top - self.close_btn_size,
+            right: rect.right + self.close_btn_size,
+            bottom: rect.bottom + self.close_btn_size,
+        }
+    }
+
+    /// Returns the height of the row of the icon, the app name and the close button.
+    fn header_height(&self) -> i32 {
+        self.margin + self.icon_size + self.margin / 2
+    }
+
+    /// Returns the width the summary and the body are wrapped to.
+    pub(crate) fn text_width(&self, has_logo: bool) -> i32 {
+        let text_left = if has_logo {
+            self.margin + self.logo_size + self.margin / 2
+        } else {
+            self.margin
+        };
+        self.width - self.margin - text_left
+    }
+
+    /// Lays out the parts of a notification showing `content`, between the minimum and maximum height.
+    pub(crate) fn layout(&self, content: &Content) -> Layout {
+        let natural = self.arrange(content, content.body_height);
+        let height = natural.height.clamp(self.min_height, self.max_height);
+        if height == natural.height {
+            return natural;
+        }
+
+        // only the body grows or shrinks, the parts below it keep their size
+        let below_body = natural.height - natural.body.bottom;
+        let body_height = (height - below_body - natural.body.top).max(0);
+        self.arrange(content, body_height)
+    }
+
+    /// Lays out the parts of a notification showing `content` with a body `body_height` pixels high.
+    fn arrange(&self, content: &Content, body_height: i32) -> Layout {
+        let header = self.header_height();
+        let content_width = self.width - self.margin * 2;
+
+        // as wide as the content, at most half as high, taller images are cropped
+        let hero = content.hero_size.map(|(width, height)| {
+            let natural = content_width as i64 * height as i64 / width.max(1) as i64;
+            let height = natural.clamp(1, content_width as i64 / 2) as i32;
+            RECT {
+                left: self.margin,
+                top: header,
+                right: self.margin + content_width,
+                bottom: header + height,
+            }
+        });
+        let content_top = hero.map_or(header, |hero| hero.bottom + self.margin / 2);
+
+        let logo = if content.has_logo {
+            Some(RECT {
+                left: self.margin,
+                top: content_top,
+                right: self.margin + self.logo_size,
+                bottom: content_top + self.logo_size,
+            })
+        } else {
+            None
+        };
+        let text_left = self.width - self.margin - self.text_width(content.has_logo);
+
+        let body_top = content_top + content.summary_height + self.margin / 2;
+        let body = RECT {
+            left: text_left,
+            top: body_top,
+            right: self.width - self.margin,
+            // the logo is never cut
+            bottom: (body_top + body_height).max(logo.map_or(0, |logo| logo.bottom)),
+        };
+        let mut bottom = body.bottom;
+
+        let progress = if content.has_progress {
+            let text = RECT {
+                left: self.margin,
+                top: bottom,
+                right: self.width - self.margin,
+                bottom: bottom + self.body_font_size,
+            };
+            let bar_top = text.bottom + self.margin / 2;
+            let bar = RECT {
+                left: self.margin,
+                top: bar_top,
+                right: self.width - self.margin,
+                bottom: bar_top + self.progress_bar_height,
+            };
+            bottom = bar.bottom;
+            Some((text, bar))
+        } else {
+            None
+        };
+        bottom += self.margin;
+
+        let more = if content.more_height > 0 {
+            let more = RECT {
+                left: self.margin,
+                top: bottom,
+                right: self.width - self.margin,
+                bottom: bottom + content.more_height,
+            };
+            bottom = more.bottom + self.margin;
+            Some(more)
+        } else {
+            None
+        };
+
+        let reply = if content.has_reply {
+            let reply = RECT {
+                left: self.margin,
+                top: bottom,
+                right: self.width - self.margin,
+                bottom: bottom + self.reply_input_height,
+            };
+            bottom = reply.bottom + self.margin;
+            Some(reply)
+        } else {
+            None
+        };
+
+        let actions = self.action_rects(content.action_count, bottom);
+        if let Some(last) = actions.last() {
+            bottom = last.bottom + self.margin;
+        }
+
+        Layout {
+            height: bottom,
+            hero,
+            logo,
+            summary_origin: (text_left, content_top),
+            body,
+            progress,
+            more,
+            reply,
+            actions,
+        }
+    }
+
+    /// Returns the rects of `count` action buttons, laid out in a row starting at `top`.
+    fn action_rects(&self, count: usize, top: i32) -> Vec<RECT> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let count = count as i32;
+        let width = (self.width - self.margin * 2 - self.action_btn_gap * (count - 1)) / count;
+        (0..count)
+            .map(|i| {
+                let left = self.margin + i * (width + self.action_btn_gap);
+                RECT {
+                    left,
+                    top,
+                    right: left + width,
+                    bottom: top + self.action_btn_height,
+                }
+            })
+            .collect()
+    }
+}
+
+/// What a notification shows besides its header, the layout depends on it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Content {
+    pub(crate) action_count: usize,
+    pub(crate) has_progress: bool,
+    /// The `(width, height)` of the hero image.
+    pub(crate) hero_size: Option<(u32, u32)>,
+    pub(crate) has_logo: bool,
+    pub(crate) has_reply: bool,
+    /// The height of the summary and of the body wrapped to [`Metrics::text_width`].
+    pub(crate) summary_height: i32,
+    pub(crate) body_height: i32,
+    /// The height of the "+N more" line of a collapsed burst, `0` without one.
+    pub(crate) more_height: i32,
+}
+
+/// The rects of the parts of a notification, in client coordinates.
+#[derive(Clone)]
+pub(crate) struct Layout {
+    pub(crate) height: i32,
+    pub(crate) hero: Option<RECT>,
+    pub(crate) logo: Option<RECT>,
+    /// Where the summary is drawn, right of the logo if any.
+    pub(crate) summary_origin: (i32, i32),
+    pub(crate) body: RECT,
+    /// The rect of the status and label texts and the rect of the bar.
+    pub(crate) progress: Option<(RECT, RECT)>,
+    /// The rect of the "+N more" line, clicking it expands the burst.
+    pub(crate) more: Option<RECT>,
+    /// The rect of the reply input.
+    pub(crate) reply: Option<RECT>,
+    pub(crate) actions: Vec<RECT>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(r: RECT) -> (i32, i32, i32, i32) {
+        (r.left, r.top, r.right, r.bottom)
+    }
+
+    /// A one line summary and a three lines body.
+    fn text() -> Content {
+        Content {
+            summary_height: 20,
+            body_height: 60,
+            ..Content::default()
+        }
+    }
+
+    fn actions(count: usize) -> Content {
+        Content {
+            action_count: count,
+            ..text()
+        }
+    }
+
+    #[test]
+    fn scale_rounds_to_nearest_pixel() {
+        assert_eq!(scale(16, 96), 16);
+        assert_eq!(scale(16, 144), 24);
+        assert_eq!(scale(15, 144), 23);
+        assert_eq!(scale(17, 120), 21);
+        assert_eq!(scale(-15, 144), -23);
+        assert_eq!(scale(360, 192), 720);
+    }
+
+    #[test]
+    fn metrics_at_base_dpi_are_the_design_values() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        assert_eq!(
+            (m.width, m.min_height, m.max_height, m.margin, m.icon_size),
+            (360, 100, 480, 16, 16)
+        );
+        assert_eq!(rect(m.close_btn_rect()), (336, 16, 344, 24));
+        assert_eq!(rect(m.close_btn_hit_rect()), (328, 8, 352, 32));
+        let layout = m.layout(&text());
+        assert_eq!(layout.height, 144);
+        assert_eq!(layout.summary_origin, (16, 40));
+        assert_eq!(rect(layout.body), (16, 68, 344, 128));
+        assert!(layout.hero.is_none() && layout.logo.is_none() && layout.progress.is_none());
+        assert_eq!(m.layout(&actions(2)).height, 192);
+    }
+
+    #[test]
+    fn metrics_scale_with_dpi() {
+        let m = Metrics::new(144, &Theme::dark());
+        assert_eq!(
+            (m.width, m.min_height, m.max_height, m.margin, m.icon_size),
+            (540, 150, 720, 24, 24)
+        );
+        assert_eq!(
+            (m.appname_font_size, m.summary_font_size, m.body_font_size),
+            (23, 26, 26)
+        );
+        assert_eq!(rect(m.close_btn_rect()), (504, 24, 516, 36));
+        assert_eq!(m.close_btn_pen_width, 3);
+        assert_eq!(m.layout(&actions(1)).height, 248);
+    }
+
+    #[test]
+    fn height_follows_the_text() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+
+        // short text is stretched to the minimum height
+        let layout = m.layout(&Content::default());
+        assert_eq!(layout.height, 100);
+        assert_eq!(rect(layout.body), (16, 48, 344, 84));
+
+        // long text is cut, the actions stay visible
+        let layout = m.layout(&Content {
+            body_height: 1000,
+            ..actions(2)
+        });
+        assert_eq!(layout.height, 480);
+        assert_eq!(rect(layout.body), (16, 68, 344, 416));
+        assert_eq!(rect(layout.actions[0]), (16, 432, 178, 464));
+    }
+
+    #[test]
+    fn action_buttons_fill_the_row() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        assert!(m.layout(&actions(0)).actions.is_empty());
+
+        let rects: Vec<_> = m
+            .layout(&actions(2))
+            .actions
+            .into_iter()
+            .map(rect)
+            .collect();
+        assert_eq!(rects, [(16, 144, 178, 176), (182, 144, 344, 176)]);
+
+        let rects = m.layout(&actions(5)).actions;
+        assert_eq!(rects.len(), 5);
+        assert!(rects[4].right <= m.width - m.margin);
+    }
+
+    #[test]
+    fn progress_sits_between_the_body_and_the_actions() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        let content = Content {
+            has_progress: true,
+            ..text()
+        };
+        let layout = m.layout(&content);
+        let (text, bar) = layout.progress.unwrap();
+        assert_eq!(rect(text), (16, 128, 344, 145));
+        assert_eq!(rect(bar), (16, 153, 344, 157));
+        assert_eq!(layout.height, 173);
+
+        let layout = m.layout(&Content {
+            action_count: 1,
+            ..content
+        });
+        let rects: Vec<_> = layout.actions.into_iter().map(rect).collect();
+        assert_eq!(rects, [(16, 173, 344, 205)]);
+        assert_eq!(layout.height, 221);
+    }
+
+    #[test]
+    fn reply_input_sits_above_the_actions() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        let layout = m.layout(&Content {
+            has_reply: true,
+            ..actions(1)
+        });
+        assert_eq!(rect(layout.reply.unwrap()), (16, 144, 344, 176));
+        assert_eq!(rect(layout.actions[0]), (16, 192, 344, 224));
+        assert_eq!(layout.height, 240);
+        assert!(m.layout(&text()).reply.is_none());
+    }
+
+    #[test]
+    fn more_line_sits_below_the_body() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        let layout = m.layout(&Content {
+            more_height: 17,
+            has_reply: true,
+            ..text()
+        });
+        assert_eq!(rect(layout.more.unwrap()), (16, 144, 344, 161));
+        assert_eq!(rect(layout.reply.unwrap()), (16, 177, 344, 209));
+        assert_eq!(layout.height, 225);
+        assert!(m.layout(&text()).more.is_none());
+    }
+
+    #[test]
+    fn hero_image_pushes_the_text_down() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        let layout = m.layout(&Content {
+            hero_size: Some((656, 246)),
+            ..text()
+        });
+        assert_eq!(rect(layout.hero.unwrap()), (16, 40, 344, 163));
+        assert_eq!(layout.summary_origin, (16, 171));
+        assert_eq!(rect(layout.body), (16, 199, 344, 259));
+        assert_eq!(layout.height, 275);
+
+        // taller images are cropped to 2:1
+        let layout = m.layout(&Content {
+            hero_size: Some((100, 100)),
+            ..text()
+        });
+        assert_eq!(rect(layout.hero.unwrap()), (16, 40, 344, 204));
+    }
+
+    #[test]
+    fn logo_sits_left_of_the_text() {
+        let m = Metrics::new(BASE_DPI, &Theme::dark());
+        assert_eq!((m.text_width(false), m.text_width(true)), (328, 272));
+
+        let layout = m.layout(&Content {
+            has_logo: true,
+            ..text()
+        });
+        assert_eq!(rect(layout.logo.unwrap()), (16, 40, 64, 88));
+        assert_eq!(layout.summary_origin, (72, 40));
+        assert_eq!(rect(layout.body), (72, 68, 344, 128));
+        assert_eq!(layout.height, 144);
+
+        // the logo is never cut
+        let layout = m.layout(&Content {
+            has_logo: true,
+            ..Content::default()
+        });
+        assert_eq!(rect(layout.body), (72, 48, 344, 88));
+        assert_eq!(layout.height, 104);
+    }
+
+    #[test]
+    fn theme_sizes_are_scaled() {
+        let mut theme = Theme::light();
+        theme.margin = 10;
+        theme.body_font_size = 20;
+        theme.corner_radius = 8;
+        let m = Metrics::new(144, &theme);
+        assert_eq!((m.margin, m.body_font_size, m.corner_radius), (15, 30, 12));
+        assert_eq!(rect(m.close_btn_rect()), (518, 15, 530, 27));
+    }
+}
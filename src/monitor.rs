@@ -0,0 +1,59 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use windows_sys::Win32::{
+    Foundation::{HWND, POINT},
+    Graphics::Gdi::{MonitorFromPoint, MonitorFromWindow, HMONITOR, MONITOR_DEFAULTTONEAREST},
+    UI::WindowsAndMessaging::{GetCursorPos, GetForegroundWindow},
+};
+
+use crate::util;
+
+/// Describes which monitor a notification is shown on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MonitorPolicy {
+    /// The primary monitor.
+    Primary,
+
+    /// The monitor under the mouse cursor.
+    Cursor,
+
+    /// The monitor of the given window, the value is a raw `HWND`.
+    Window(isize),
+
+    /// The monitor of the window the user is currently working with.
+    Foreground,
+}
+
+impl Default for MonitorPolicy {
+    fn default() -> Self {
+        MonitorPolicy::Primary
+    }
+}
+
+impl MonitorPolicy {
+    /// Returns the monitor selected by this policy, falling back to the primary monitor.
+    pub(crate) unsafe fn resolve(self) -> HMONITOR {
+        match self {
+            MonitorPolicy::Primary => util::primary_monitor(),
+            MonitorPolicy::Cursor => {
+                let mut pt = POINT { x: 0, y: 0 };
+                if GetCursorPos(&mut pt) == 0 {
+                    return util::primary_monitor();
+                }
+                MonitorFromPoint(pt, MONITOR_DEFAULTTONEAREST)
+            }
+            MonitorPolicy::Window(hwnd) => monitor_from_window(hwnd),
+            MonitorPolicy::Foreground => monitor_from_window(GetForegroundWindow()),
+        }
+    }
+}
+
+unsafe fn monitor_from_window(hwnd: HWND) -> HMONITOR {
+    if hwnd == 0 {
+        util::primary_monitor()
+    } else {
+        MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST)
+    }
+}
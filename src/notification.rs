@@ -3,7 +3,15 @@
 // SPDX-License-Identifier: MIT
 
 use once_cell::sync::Lazy;
-use std::{ptr, sync::Mutex, thread, time::Duration};
+use std::{
+    cell::Cell,
+    fmt, ptr,
+    sync::{
+        atomic::{AtomicIsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 use windows_sys::{
     w,
     Win32::{
@@ -13,47 +21,137 @@ use windows_sys::{
         System::LibraryLoader::*,
         UI::{
             Controls::*,
+            Input::KeyboardAndMouse::{
+                ReleaseCapture, SetCapture, SetFocus, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT,
+                VK_ESCAPE, VK_RETURN,
+            },
+            Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
             WindowsAndMessaging::{self as w32wm, *},
         },
     },
 };
 
 use crate::{
-    timeout::Timeout,
-    util::{self, GetWindowLongPtrW, SetWindowLongPtrW, GET_X_LPARAM, GET_Y_LPARAM, RGB},
+    action::{Action, MAX_ACTIONS},
+    animation::{self, Tween, FADE_OUT, FRAME_INTERVAL, GLIDE, SLIDE_IN},
+    burst::{self, Arrival, Collapse, BURST_GAP, BURST_MIN_COUNT},
+    close_reason::CloseReason,
+    error::Error,
+    handle::NotificationHandle,
+    image::{Image, LogoCrop},
+    layout, manager,
+    metrics::{Content, Layout, Metrics},
+    monitor::MonitorPolicy,
+    progress::Progress,
+    queue::Queue,
+    quiet::{Admission, QuietMode},
+    sound::Sound,
+    text,
+    theme::{self, Theme},
+    timeout::{Countdown, Timeout},
+    urgency::Urgency,
+    util::{self, GetWindowLongPtrW, SetWindowLongPtrW, TextMeasurer, GET_X_LPARAM, GET_Y_LPARAM},
 };
 
-/// notification width
-const NW: i32 = 360;
-/// notification height
-const NH: i32 = 170;
-/// notification margin
-const NM: i32 = 16;
-/// notification icon size (width or height)
-const NIS: i32 = 16;
-/// notification window bg color
-const WC: u32 = RGB(50, 57, 69);
-/// used for notification summary (title)
-const TC: u32 = RGB(255, 255, 255);
-/// used for notification body
-const SC: u32 = RGB(200, 200, 200);
-
-const CLOSE_BTN_RECT: RECT = RECT {
-    left: NW - NM - NM / 2,
-    top: NM,
-    right: (NW - NM - NM / 2) + 8,
-    bottom: NM + 8,
-};
-const CLOSE_BTN_RECT_EXTRA: RECT = RECT {
-    left: CLOSE_BTN_RECT.left - 8,
-    top: CLOSE_BTN_RECT.top - 8,
-    right: CLOSE_BTN_RECT.right + 8,
-    bottom: CLOSE_BTN_RECT.bottom + 8,
-};
+/// Posted by [`NotificationHandle::update`], the new content is read from the handle.
+pub(crate) const WM_UPDATE_NOTIFICATION: u32 = WM_USER + 1;
+/// Posted to restack the notifications outside of a `SetWindowPos` call which may hold [`ACTIVE_NOTIFICATIONS`].
+const WM_REPOSITION_NOTIFICATIONS: u32 = WM_USER + 2;
+/// Posted when the theme of the notifications changed, the notification resizes and repaints itself.
+const WM_REFRESH_THEME: u32 = WM_USER + 4;
+/// Posted to move a notification to a new position in the stack, `x` is in `WPARAM` and `y` in `LPARAM`.
+const WM_MOVE_NOTIFICATION: u32 = WM_USER + 3;
+/// Posted by the reply input when the user presses enter.
+const WM_SUBMIT_REPLY: u32 = WM_USER + 5;
+/// Posted when a notification with the same tag is shown, the new notification is read from the handle.
+pub(crate) const WM_REPLACE_NOTIFICATION: u32 = WM_USER + 6;
+/// Posted when the notification is collapsed or expanded, the number of notifications it stands for is in `WPARAM`
+/// and whether it is hidden in `LPARAM`.
+const WM_COLLAPSE_NOTIFICATION: u32 = WM_USER + 7;
+/// Posted to the queue window of a thread to show the notifications it queued, see [`queue_window`].
+const WM_SHOW_QUEUED: u32 = WM_USER + 8;
+
+/// Id of the reply input among the children of a notification, and of its subclass.
+const REPLY_INPUT_ID: usize = 1;
+
+/// Id of the timer driving the animations of a notification.
+const ANIMATION_TIMER_ID: usize = 1;
+/// Id of the timer closing a notification when its timeout expires.
+const TIMEOUT_TIMER_ID: usize = 2;
+/// Id of the timer animating an indeterminate progress bar.
+const PROGRESS_TIMER_ID: usize = 3;
+
+/// How often the end of the quiet time is checked while notifications are held back.
+const QUIET_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The notification whose sound played last, a sound plays until another one starts.
+static SOUND_OWNER: AtomicIsize = AtomicIsize::new(0);
+
+static QUEUE: Lazy<Mutex<Queue<QueuedNotification>>> = Lazy::new(|| Mutex::new(Queue::new()));
+
+thread_local! {
+    /// The timer of the thread showing the notifications held back during the quiet time, `0` if not running.
+    static QUIET_TIMER: Cell<usize> = Cell::new(0);
+    /// The message-only window of the thread, see [`queue_window`].
+    static QUEUE_WINDOW: Cell<HWND> = Cell::new(0);
+}
+
+static ACTIVE_NOTIFICATIONS: Lazy<Mutex<Vec<ActiveNotification>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A shown notification window, ordered from the oldest to the newest in [`ACTIVE_NOTIFICATIONS`].
+struct ActiveNotification {
+    hwnd: HWND,
+    /// The monitor the notification is stacked on.
+    monitor: HMONITOR,
+    urgency: Urgency,
+    tag: Option<String>,
+    group: Option<String>,
+    handle: NotificationHandle,
+    /// Notifications of the same app shown together are collapsed, see
+    /// [`NotificationManager::set_collapse_bursts`](crate::NotificationManager::set_collapse_bursts).
+    appname: String,
+    shown_at: Instant,
+    /// Set once the user expanded the collapsed notifications.
+    expanded: bool,
+    /// Whether the notification is hidden in a collapsed burst.
+    hidden: bool,
+}
 
-static ACTIVE_NOTIFICATIONS: Lazy<Mutex<Vec<HWND>>> = Lazy::new(|| Mutex::new(Vec::new()));
-static PRIMARY_MONITOR: Lazy<Mutex<MONITORINFOEXW>> =
-    Lazy::new(|| unsafe { Mutex::new(util::get_monitor_info(util::primary_monitor())) });
+impl ActiveNotification {
+    fn arrival(&self) -> Arrival<(HMONITOR, &str)> {
+        Arrival {
+            key: (self.monitor, &self.appname),
+            at: self.shown_at,
+            expanded: self.expanded,
+            hidden: self.hidden,
+        }
+    }
+}
+
+/// A notification waiting for room on the screen.
+struct QueuedNotification {
+    notification: Notification,
+    handle: NotificationHandle,
+    /// The queue window of the thread that showed the notification, its window is created on that thread.
+    thread_window: HWND,
+    /// Whether the notification replaces the one of `handle` still on the screen,
+    /// it is held back for the quiet time, see [`Notification::tag`].
+    replaces: bool,
+}
+
+impl QueuedNotification {
+    /// Closes the notification without showing it, its `on_close` callback is called with `reason`.
+    fn cancel(self, reason: CloseReason) {
+        if let Some(on_close) = self.notification.on_close.take() {
+            on_close(reason);
+        }
+        // the replaced notification stays on the screen
+        if !self.replaces {
+            self.handle.set_closed(reason);
+        }
+    }
+}
 
 /// Describes The notification
 #[non_exhaustive]
@@ -67,6 +165,33 @@ pub struct Notification {
     pub body: String,
     pub timeout: Timeout,
     pub silent: bool,
+    pub actions: Vec<Action>,
+    pub monitor: MonitorPolicy,
+    /// The theme of the notification, the one of the [`NotificationManager`](crate::NotificationManager) if `None`.
+    pub theme: Option<Theme>,
+    pub progress: Option<Progress>,
+    /// A large image shown above the text.
+    pub image: Option<Image>,
+    /// Shown left of the text instead of the icon.
+    pub app_logo_override: Option<Image>,
+    pub app_logo_crop: LogoCrop,
+    /// The maximum number of lines of the body, as many as fit in the notification if `None`.
+    pub max_body_lines: Option<usize>,
+    pub urgency: Urgency,
+    pub sound: Sound,
+    /// Whether the sound plays again and again until the notification closes.
+    pub loop_sound: bool,
+    /// The placeholder of the reply input, the notification has one if `Some`.
+    pub reply_placeholder: Option<String>,
+    pub tag: Option<String>,
+    pub group: Option<String>,
+    /// Whether the sound is skipped when the notification replaces one with the same tag.
+    pub silent_replace: bool,
+    on_click: Callback<ClickCallback>,
+    on_action: Callback<ActionCallback>,
+    on_close: Callback<CloseCallback>,
+    on_dismiss: Callback<CloseCallback>,
+    on_reply: Callback<ReplyCallback>,
 }
 
 impl Default for Notification {
@@ -80,6 +205,26 @@ impl Default for Notification {
             icon_width: 32,
             timeout: Timeout::Default,
             silent: false,
+            actions: Vec::new(),
+            monitor: MonitorPolicy::Primary,
+            theme: None,
+            progress: None,
+            image: None,
+            app_logo_override: None,
+            app_logo_crop: LogoCrop::Square,
+            max_body_lines: None,
+            urgency: Urgency::Normal,
+            sound: Sound::Default,
+            loop_sound: false,
+            reply_placeholder: None,
+            tag: None,
+            group: None,
+            silent_replace: false,
+            on_click: Callback::default(),
+            on_action: Callback::default(),
+            on_close: Callback::default(),
+            on_dismiss: Callback::default(),
+            on_reply: Callback::default(),
         }
     }
 }
@@ -120,176 +265,1607 @@ impl Notification {
     /// Set the `icon` field from 32bpp RGBA data.
     ///
     /// The length of `rgba` must be divisible by 4, and `width * height` must equal
-    /// `rgba.len() / 4`. Otherwise, this will panic.
+    /// `rgba.len() / 4`. Otherwise, this will panic, see [`Notification::try_icon`]
+    /// for a fallible version.
     pub fn icon(&mut self, rgba: Vec<u8>, width: u32, height: u32) -> &mut Notification {
-        if rgba.len() % util::PIXEL_SIZE != 0 {
-            panic!("The length of `rgba` is not divisible by 4");
-        }
-        let pixel_count = rgba.len() / util::PIXEL_SIZE;
-        if pixel_count != (width * height) as usize {
-            panic!("`width * height` is not equal `rgba.len() / 4`");
-        } else {
-            self.icon = Some(rgba);
-            self.icon_width = width;
-            self.icon_height = height;
+        if let Err(e) = self.try_icon(rgba, width, height) {
+            panic!("{}", e);
         }
         self
     }
 
+    /// Set the `icon` field from 32bpp RGBA data.
+    ///
+    /// Returns [`Error::InvalidIcon`] if the length of `rgba` is not divisible by 4
+    /// or `width * height` is not equal to `rgba.len() / 4`.
+    pub fn try_icon(
+        &mut self,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<&mut Notification, Error> {
+        util::check_rgba(rgba.len(), width, height)?;
+        self.icon = Some(rgba);
+        self.icon_width = width;
+        self.icon_height = height;
+        Ok(self)
+    }
+
+    /// Limit the body to `lines` lines, the last one ends with an ellipsis when the body is cut.
+    ///
+    /// Without a limit, the body is cut when the notification reaches its maximum height.
+    pub fn max_body_lines(&mut self, lines: usize) -> &mut Notification {
+        self.max_body_lines = Some(lines);
+        self
+    }
+
+    /// Set the urgency of the notification, defaults to [`Urgency::Normal`].
+    pub fn urgency(&mut self, urgency: Urgency) -> &mut Notification {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Identify the notification with a tag, unique within its [`Notification::group`].
+    ///
+    /// Showing a notification with the tag and group of a notification still on the screen
+    /// replaces that notification in place instead of stacking a new one, and returns its handle.
+    /// During Do Not Disturb or quiet hours, the replacement is held back or dropped like a new notification.
+    pub fn tag(&mut self, tag: &str) -> &mut Notification {
+        self.tag = Some(tag.to_owned());
+        self
+    }
+
+    /// Set the group of the notification, see [`Notification::tag`] and
+    /// [`NotificationManager::remove_group`](crate::NotificationManager::remove_group).
+    pub fn group(&mut self, group: &str) -> &mut Notification {
+        self.group = Some(group.to_owned());
+        self
+    }
+
+    /// Don't play the sound when the notification replaces one with the same [`Notification::tag`].
+    pub fn silent_replace(&mut self, silent_replace: bool) -> &mut Notification {
+        self.silent_replace = silent_replace;
+        self
+    }
+
     /// Set the `timeout` field.
     pub fn timeout(&mut self, timeout: Timeout) -> &mut Notification {
         self.timeout = timeout;
         self
     }
 
+    /// Set the sound played when the notification is shown, unless it is `silent`.
+    pub fn sound(&mut self, sound: Sound) -> &mut Notification {
+        self.sound = sound;
+        self
+    }
+
+    /// Play the sound again and again until the notification closes, for alarms and calls.
+    pub fn loop_sound(&mut self, loop_sound: bool) -> &mut Notification {
+        self.loop_sound = loop_sound;
+        self
+    }
+
     /// Set the `silent` field.
     pub fn silent(&mut self, silent: bool) -> &mut Notification {
         self.silent = silent;
         self
     }
 
+    /// Add a button with the given `label` at the bottom of the notification.
+    ///
+    /// When the button is clicked, the [`Notification::on_action`] callback is called with `id`
+    /// and the notification is closed with [`CloseReason::ActionInvoked`].
+    /// At most 5 actions are shown, extra actions are ignored.
+    pub fn action(&mut self, id: &str, label: &str) -> &mut Notification {
+        if self.actions.len() < MAX_ACTIONS {
+            self.actions.push(Action {
+                id: id.to_owned(),
+                label: label.to_owned(),
+            });
+        }
+        self
+    }
+
+    /// Set a callback that is called with the id of the [`Action`] clicked by the user.
+    ///
+    /// The callback is moved into the next notification shown with this builder.
+    pub fn on_action<F: FnMut(&str) + Send + 'static>(&mut self, f: F) -> &mut Notification {
+        self.on_action = Callback::new(Box::new(f));
+        self
+    }
+
+    /// Set the monitor the notification is shown on, defaults to [`MonitorPolicy::Primary`].
+    ///
+    /// The monitor is selected when the notification is shown, if the display settings change
+    /// while it is visible, the notification moves to the nearest monitor.
+    pub fn monitor(&mut self, monitor: MonitorPolicy) -> &mut Notification {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Set the theme of the notification, overriding [`NotificationManager::set_theme`](crate::NotificationManager::set_theme).
+    pub fn theme(&mut self, theme: Theme) -> &mut Notification {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Show a progress bar below the body of the notification.
+    ///
+    /// Update it with [`NotificationHandle::update`] to track a job with a single notification.
+    pub fn progress(&mut self, progress: Progress) -> &mut Notification {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Show a large image from 32bpp RGBA data above the text of the notification.
+    ///
+    /// The image spans the width of the notification and is at most half as high,
+    /// taller images are cropped around their center.
+    ///
+    /// The length of `rgba` must be divisible by 4, and `width * height` must equal
+    /// `rgba.len() / 4`. Otherwise, this will panic, see [`Notification::try_image`]
+    /// for a fallible version.
+    pub fn image(&mut self, rgba: Vec<u8>, width: u32, height: u32) -> &mut Notification {
+        if let Err(e) = self.try_image(rgba, width, height) {
+            panic!("{}", e);
+        }
+        self
+    }
+
+    /// Show a large image from 32bpp RGBA data above the text of the notification.
+    ///
+    /// Returns [`Error::InvalidImage`] if the length of `rgba` is not divisible by 4
+    /// or `width * height` is not equal to `rgba.len() / 4`.
+    pub fn try_image(
+        &mut self,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<&mut Notification, Error> {
+        self.image = Some(Image::from_rgba(rgba, width, height)?);
+        Ok(self)
+    }
+
+    /// Show a logo from 32bpp RGBA data left of the text, cropped to a square or a circle.
+    ///
+    /// Non-square images are cropped around their center.
+    ///
+    /// The length of `rgba` must be divisible by 4, and `width * height` must equal
+    /// `rgba.len() / 4`. Otherwise, this will panic, see [`Notification::try_app_logo_override`]
+    /// for a fallible version.
+    pub fn app_logo_override(
+        &mut self,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        crop: LogoCrop,
+    ) -> &mut Notification {
+        if let Err(e) = self.try_app_logo_override(rgba, width, height, crop) {
+            panic!("{}", e);
+        }
+        self
+    }
+
+    /// Show a logo from 32bpp RGBA data left of the text, cropped to a square or a circle.
+    ///
+    /// Returns [`Error::InvalidImage`] if the length of `rgba` is not divisible by 4
+    /// or `width * height` is not equal to `rgba.len() / 4`.
+    pub fn try_app_logo_override(
+        &mut self,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        crop: LogoCrop,
+    ) -> Result<&mut Notification, Error> {
+        self.app_logo_override = Some(Image::from_rgba(rgba, width, height)?);
+        self.app_logo_crop = crop;
+        Ok(self)
+    }
+
+    /// Set a callback that is called when the user clicks the body of the notification.
+    ///
+    /// Clicking the body closes the notification with [`CloseReason::Clicked`].
+    /// The callback is moved into the next notification shown with this builder.
+    pub fn on_click<F: FnMut() + Send + 'static>(&mut self, f: F) -> &mut Notification {
+        self.on_click = Callback::new(Box::new(f));
+        self
+    }
+
+    /// Set a callback that is called exactly once when the notification is closed, whatever the reason.
+    ///
+    /// When the notification is replaced by one with the same [`Notification::tag`] and its own `on_close` callback,
+    /// the callback is called with [`CloseReason::Replaced`], otherwise it carries over to the replacement.
+    ///
+    /// The callback is moved into the next notification shown with this builder.
+    pub fn on_close<F: FnOnce(CloseReason) + Send + 'static>(&mut self, f: F) -> &mut Notification {
+        self.on_close = Callback::new(Box::new(f));
+        self
+    }
+
+    /// Set a callback that is called exactly once when the notification is closed by the user
+    /// with the close button ([`CloseReason::Dismissed`]) or because it expired ([`CloseReason::TimedOut`]).
+    ///
+    /// A callback replaced along with the notification, see [`Notification::on_close`], isn't called.
+    ///
+    /// The callback is moved into the next notification shown with this builder.
+    pub fn on_dismiss<F: FnOnce(CloseReason) + Send + 'static>(
+        &mut self,
+        f: F,
+    ) -> &mut Notification {
+        self.on_dismiss = Callback::new(Box::new(f));
+        self
+    }
+
+    /// Show a text box to reply from the notification, `placeholder` is shown while it is empty.
+    ///
+    /// Pressing enter in the text box closes the notification with [`CloseReason::Replied`],
+    /// the text is passed to the [`Notification::on_reply`] callback and returned by
+    /// [`NotificationHandle::reply`]. Pressing escape dismisses the notification.
+    pub fn reply_input(&mut self, placeholder: &str) -> &mut Notification {
+        self.reply_placeholder = Some(placeholder.to_owned());
+        self
+    }
+
+    /// Set a callback that is called with the text the user replied with, see [`Notification::reply_input`].
+    ///
+    /// The callback is moved into the next notification shown with this builder.
+    pub fn on_reply<F: FnMut(&str) + Send + 'static>(&mut self, f: F) -> &mut Notification {
+        self.on_reply = Callback::new(Box::new(f));
+        self
+    }
+
     /// Shows the Notification.
     ///
     /// Requires a win32 event_loop to be running on the thread, otherwise the notification will close immediately.
-    pub fn show(&self) -> Result<(), u32> {
+    ///
+    /// Returns a [`NotificationHandle`] that can be used to close, update or wait for the notification.
+    ///
+    /// When there is no room left on the screen, see [`NotificationManager::set_max_visible`](crate::NotificationManager::set_max_visible),
+    /// the notification waits in a queue and is shown once earlier notifications close.
+    /// During Do Not Disturb or quiet hours, the notification is held back according to
+    /// [`NotificationManager::set_quiet_mode`](crate::NotificationManager::set_quiet_mode).
+    pub fn show(&self) -> Result<NotificationHandle, Error> {
+        if let Some(icon) = &self.icon {
+            util::check_rgba(icon.len(), self.icon_width, self.icon_height)?;
+        }
+
+        let quiet = manager::settings().quiet;
+        match quiet.admit(self.urgency, manager::is_quiet(&quiet)) {
+            Admission::Show => {
+                if let Some(handle) = self.replace_tagged(false) {
+                    return Ok(handle);
+                }
+            }
+            Admission::Queue => {
+                let handle = self.replace_tagged(true).unwrap_or_else(|| {
+                    let handle = NotificationHandle::new();
+                    self.enqueue(handle.clone(), false);
+                    handle
+                });
+                unsafe { start_quiet_timer() };
+                return Ok(handle);
+            }
+            // a replacement is dropped too, the notification it replaces stays as it is
+            Admission::Suppress => {
+                let handle = NotificationHandle::new();
+                let suppressed = QueuedNotification {
+                    notification: self.clone(),
+                    handle: handle.clone(),
+                    thread_window: 0,
+                    replaces: false,
+                };
+                suppressed.cancel(CloseReason::Suppressed);
+                return Ok(handle);
+            }
+        }
+
+        // earlier notifications go first
+        show_queued();
+        let handle = NotificationHandle::new();
         unsafe {
-            let hinstance = GetModuleHandleW(ptr::null());
+            let queue_len = QUEUE.lock().map_or(0, |queue| queue.len());
+            if queue_len > 0 {
+                self.enqueue(handle.clone(), false);
+            } else {
+                self.show_window(&handle, false)?;
+            }
+        }
+        Ok(handle)
+    }
+
+    /// Shows the notification in a new window, or queues it if there is no room left on the screen.
+    ///
+    /// Returns whether the notification was shown, a notification `requeued` goes back to the front of the queue.
+    unsafe fn show_window(
+        &self,
+        handle: &NotificationHandle,
+        requeued: bool,
+    ) -> Result<bool, Error> {
+        let hinstance = GetModuleHandleW(ptr::null());
+
+        let class_name = w!("win7-notifications");
+        let wnd_class = WNDCLASSEXW {
+            lpfnWndProc: Some(window_proc),
+            lpszClassName: class_name,
+            hInstance: hinstance,
+            hbrBackground: 0, // the background is painted with the theme on `WM_ERASEBKGND`
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style: CS_HREDRAW | CS_VREDRAW | CS_OWNDC,
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hIcon: 0,
+            hCursor: 0, // must be null in order for cursor state to work properly
+            lpszMenuName: ptr::null(),
+            hIconSm: 0,
+        };
+        if RegisterClassExW(&wnd_class) == 0 {
+            let error = GetLastError();
+            // the class is registered only once per process
+            if error != ERROR_CLASS_ALREADY_EXISTS {
+                return Err(Error::RegisterClass(error));
+            }
+        }
+
+        let monitor = self.monitor.resolve();
+        let monitor_info = util::get_monitor_info(monitor)?.monitorInfo;
+
+        let taskbar_list = util::taskbar_list()?;
+
+        let settings = manager::settings();
+        let theme = resolve_theme(self);
+        let metrics = Metrics::new(util::get_monitor_dpi(monitor), &theme);
+        let width = metrics.width;
+        let (layout, text) = self.layout(&theme, &metrics, 0);
+        let height = layout.height;
+        if !has_room(monitor, monitor_info.rcWork, (width, height), &settings) {
+            self.enqueue(handle.clone(), requeued);
+            return Ok(false);
+        }
+        let rect = layout::layout(
+            monitor_info.rcWork,
+            monitor_info.rcMonitor,
+            settings.position,
+            settings.stack_direction,
+            &[(width, height)],
+        )[0];
+
+        let animate = animations_enabled();
+        let (x, y) = if animate {
+            animation::slide_in_origin(rect, monitor_info.rcWork)
+        } else {
+            (rect.left, rect.top)
+        };
+        let started = Instant::now();
+        let countdown = Countdown::start(self.urgency.timeout(self.timeout), started);
+
+        let data = WindowData {
+            window: 0,
+            mouse_hovering_close_btn: false,
+            mouse_inside: false,
+            hovered_action: None,
+            pressed_action: None,
+            notification: self.clone(),
+            theme,
+            metrics,
+            layout,
+            text,
+            on_click: self.on_click.take(),
+            on_action: self.on_action.take(),
+            on_close: self.on_close.take(),
+            on_dismiss: self.on_dismiss.take(),
+            on_reply: self.on_reply.take(),
+            close_reason: None,
+            handle: handle.clone(),
+            movement: if animate {
+                Some(Movement {
+                    started,
+                    x: Tween::new(x, rect.left, SLIDE_IN),
+                    y: Tween::new(y, rect.top, SLIDE_IN),
+                })
+            } else {
+                None
+            },
+            fade: if animate {
+                Some(Fade {
+                    started,
+                    alpha: Tween::new(0, u8::MAX.into(), SLIDE_IN),
+                })
+            } else {
+                None
+            },
+            alpha: if animate { 0 } else { u8::MAX },
+            closing: false,
+            countdown,
+            shown_at: started,
+            scaled_images: None,
+            reply_input: 0,
+            reply_font: 0,
+            reply_focused: false,
+            more: 0,
+        };
+
+        let hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_LAYERED,
+            class_name,
+            w!("win7-notifications-window"),
+            WS_SYSMENU | WS_CAPTION,
+            x,
+            y,
+            width,
+            height,
+            0,
+            0,
+            hinstance,
+            Box::into_raw(Box::new(data)) as _,
+        );
+
+        if hwnd == 0 {
+            return Err(Error::CreateWindow(GetLastError()));
+        }
+
+        set_corner_region(hwnd, metrics, width, height);
+
+        // a layered window isn't drawn until its attributes are set
+        SetLayeredWindowAttributes(hwnd, 0, if animate { 0 } else { u8::MAX }, LWA_ALPHA);
+
+        // reposition active notifications and make room for new one
+        if let Ok(mut active_notifications) = ACTIVE_NOTIFICATIONS.lock() {
+            active_notifications.push(ActiveNotification {
+                hwnd,
+                monitor,
+                urgency: self.urgency,
+                tag: self.tag.clone(),
+                group: self.group.clone(),
+                handle: handle.clone(),
+                appname: self.appname.clone(),
+                shown_at: started,
+                expanded: false,
+                hidden: false,
+            });
+            reposition_notifications(&mut active_notifications);
+        }
+
+        // shadows
+        let mut is_dwm_enabled = 0;
+        DwmIsCompositionEnabled(&mut is_dwm_enabled);
+        if is_dwm_enabled == 1 {
+            let margins = MARGINS {
+                cxLeftWidth: 1,
+                cxRightWidth: 0,
+                cyBottomHeight: 0,
+                cyTopHeight: 0,
+            };
+            DwmExtendFrameIntoClientArea(hwnd, &margins);
+        }
+
+        util::skip_taskbar(taskbar_list, hwnd);
+        // the notification doesn't take the focus from the active window
+        ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        if animate {
+            start_animation_timer(hwnd);
+        }
+        // the copy owned by the window, a sound in memory is read while it plays
+        let data = GetWindowLongPtrW(hwnd, GWL_USERDATA) as *mut WindowData;
+        play_sound(hwnd, &(*data).notification);
+
+        update_progress_timer(hwnd, self.progress.as_ref());
+        if let Some(countdown) = countdown {
+            set_timer(hwnd, TIMEOUT_TIMER_ID, countdown.remaining());
+        }
+
+        Ok(true)
+    }
+
+    fn enqueue(&self, handle: NotificationHandle, front: bool) {
+        if let Ok(mut queue) = QUEUE.lock() {
+            let queued = QueuedNotification {
+                notification: self.clone(),
+                handle,
+                thread_window: unsafe { queue_window() },
+                replaces: false,
+            };
+            if front {
+                queue.push_front(self.urgency, queued);
+            } else {
+                queue.push(self.urgency, queued);
+            }
+        }
+    }
+
+    /// Copies the content updated by [`NotificationHandle::update`] from `notification`.
+    fn update_content(&mut self, notification: Notification) {
+        self.appname = notification.appname;
+        self.summary = notification.summary;
+        self.body = notification.body;
+        self.icon = notification.icon;
+        self.icon_width = notification.icon_width;
+        self.icon_height = notification.icon_height;
+        self.image = notification.image;
+        self.app_logo_override = notification.app_logo_override;
+        self.app_logo_crop = notification.app_logo_crop;
+        self.progress = notification.progress;
+    }
+
+    /// Replaces the shown or queued notification with the same tag and group,
+    /// returns its handle if there is one.
+    ///
+    /// When `held`, the notification on the screen is replaced once the quiet time ends.
+    fn replace_tagged(&self, held: bool) -> Option<NotificationHandle> {
+        let tag = self.tag.as_ref()?;
+        let same_tag = |n: &Notification| n.tag.as_ref() == Some(tag) && n.group == self.group;
+
+        // a replacement held back goes first, older content must not be shown after this one
+        if let Ok(mut queue) = QUEUE.lock() {
+            if let Some(queued) = queue.find_mut(|q| same_tag(&q.notification)) {
+                queued.notification = self.clone();
+                return Some(queued.handle.clone());
+            }
+        }
+
+        let existing = ACTIVE_NOTIFICATIONS
+            .lock()
+            .ok()?
+            .iter()
+            .find(|n| n.tag.as_ref() == Some(tag) && n.group == self.group)
+            .map(|n| n.handle.clone())?;
+        if held {
+            let queued = QueuedNotification {
+                notification: self.clone(),
+                handle: existing.clone(),
+                thread_window: unsafe { queue_window() },
+                replaces: true,
+            };
+            QUEUE.lock().ok()?.push(self.urgency, queued);
+            Some(existing)
+        } else if replace_shown(self, &existing) {
+            Some(existing)
+        } else {
+            None
+        }
+    }
+
+    /// Lays out the notification, wrapping and cutting its text measured in the fonts of `theme`.
+    ///
+    /// A notification standing for `more` collapsed ones has a line telling how many.
+    unsafe fn layout(&self, theme: &Theme, metrics: &Metrics, more: usize) -> (Layout, Text) {
+        let has_logo = self.app_logo_override.is_some();
+        let width = metrics.text_width(has_logo);
+        let collapsed = self.urgency == Urgency::Low && manager::settings().collapse_low_urgency;
+        let max_body_lines = if collapsed {
+            Some(0)
+        } else {
+            self.max_body_lines
+        };
+
+        let summary_font = TextMeasurer::new(
+            &theme.font_family,
+            metrics.summary_font_size,
+            theme.title_font_weight,
+        );
+        let summary = text::truncate(&self.summary, width, |s| summary_font.width(s));
+        let summary_height = if summary.is_empty() {
+            0
+        } else {
+            summary_font.line_height()
+        };
+
+        let body_font = TextMeasurer::new(
+            &theme.font_family,
+            metrics.body_font_size,
+            theme.body_font_weight,
+        );
+        let line_height = body_font.line_height().max(1);
+        let mut body = text::wrap(&self.body, width, max_body_lines, |s| body_font.width(s));
+
+        let layout = metrics.layout(&Content {
+            action_count: self.actions.len(),
+            has_progress: self.progress.is_some(),
+            hero_size: self
+                .image
+                .as_ref()
+                .filter(|_| !collapsed)
+                .map(|i| (i.width(), i.height())),
+            has_logo,
+            has_reply: self.reply_placeholder.is_some(),
+            summary_height,
+            body_height: body.len() as i32 * line_height,
+            more_height: if more > 0 { line_height } else { 0 },
+        });
+
+        // the lines below the maximum height are cut too
+        let fitting = ((layout.body.bottom - layout.body.top) / line_height) as usize;
+        if body.len() > fitting {
+            body = text::wrap(&self.body, width, Some(fitting), |s| body_font.width(s));
+        }
+
+        let text = Text {
+            summary,
+            body,
+            body_line_height: line_height,
+        };
+        (layout, text)
+    }
+}
+
+/// Plays the sound of a notification shown in `hwnd`, unless it is silent.
+unsafe fn play_sound(hwnd: HWND, notification: &Notification) {
+    if notification.urgency.is_silent(notification.silent) {
+        return;
+    }
+
+    let flags = if notification.loop_sound {
+        SND_ASYNC | SND_LOOP
+    } else {
+        SND_ASYNC
+    };
+    match &notification.sound {
+        Sound::Default if notification.urgency == Urgency::Critical => {
+            PlaySoundW(w!("SystemHand"), 0, SND_ALIAS | flags);
+        }
+        Sound::Default => {
+            // Passing an invalid path to `PlaySoundW` will make windows play default sound.
+            // https://docs.microsoft.com/en-us/previous-versions/dd743680(v=vs.85)#remarks
+            PlaySoundW(w!("null"), GetModuleHandleW(ptr::null()), flags);
+        }
+        Sound::Alias(alias) => {
+            PlaySoundW(util::encode_wide(alias).as_ptr(), 0, SND_ALIAS | flags);
+        }
+        Sound::File(path) => {
+            PlaySoundW(util::encode_wide(path).as_ptr(), 0, SND_FILENAME | flags);
+        }
+        Sound::Memory(wav) => {
+            PlaySoundW(wav.as_ptr() as _, 0, SND_MEMORY | flags);
+        }
+    }
+    // playing a sound stops the one of the previous notification
+    SOUND_OWNER.store(hwnd, Ordering::SeqCst);
+}
+
+/// Stops the sound of a closing notification if it loops or is read from its memory.
+unsafe fn stop_sound(hwnd: HWND, notification: &Notification) {
+    let stop = notification.loop_sound || matches!(notification.sound, Sound::Memory(_));
+    if stop
+        && SOUND_OWNER
+            .compare_exchange(hwnd, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    {
+        PlaySoundW(ptr::null(), 0, 0);
+    }
+}
+
+/// Creates the reply input of a notification if it has one, see [`Notification::reply_input`].
+unsafe fn create_reply_input(hwnd: HWND, userdata: *mut WindowData) {
+    if (*userdata).notification.reply_placeholder.is_none() {
+        return;
+    }
+    let edit = CreateWindowExW(
+        0,
+        WC_EDITW,
+        ptr::null(),
+        WS_CHILD | WS_VISIBLE | WS_TABSTOP | ES_AUTOHSCROLL as u32,
+        0,
+        0,
+        0,
+        0,
+        hwnd,
+        REPLY_INPUT_ID as _,
+        GetModuleHandleW(ptr::null()),
+        ptr::null(),
+    );
+    if edit == 0 {
+        return;
+    }
+    SetWindowSubclass(edit, Some(reply_input_proc), REPLY_INPUT_ID, 0);
+    (*userdata).reply_input = edit;
+    (*userdata).update_reply_font();
+    (*userdata).place_reply_input();
+}
 
-            let class_name = w!("win7-notifications");
+/// Draws the placeholder of an empty reply input over the background painted by the edit control.
+///
+/// `EM_SETCUEBANNER` isn't used as it needs version 6 of the common controls,
+/// which apps only get with a manifest.
+unsafe fn paint_reply_placeholder(edit: HWND) {
+    if GetWindowTextLengthW(edit) > 0 {
+        return;
+    }
+    let userdata = GetWindowLongPtrW(GetParent(edit), GWL_USERDATA) as *mut WindowData;
+    if userdata.is_null() {
+        return;
+    }
+    let placeholder = match &(*userdata).notification.reply_placeholder {
+        Some(placeholder) => placeholder,
+        None => return,
+    };
+    let theme = theme::paint_theme(&(*userdata).theme, &util::Win32SystemSettings);
+
+    let mut rc: RECT = std::mem::zeroed();
+    SendMessageW(edit, EM_GETRECT, 0, &mut rc as *mut RECT as LPARAM);
+    let hdc = GetDC(edit);
+    let old_hfont = SelectObject(hdc, SendMessageW(edit, WM_GETFONT, 0, 0) as HGDIOBJ);
+    SetBkMode(hdc, TRANSPARENT as _);
+    SetTextColor(hdc, theme.appname_color.to_colorref());
+    let mut text = util::encode_wide(placeholder);
+    DrawTextW(
+        hdc,
+        text.as_mut_ptr(),
+        -1,
+        &mut rc,
+        DT_LEFT | DT_SINGLELINE | DT_NOPREFIX | DT_END_ELLIPSIS,
+    );
+    SelectObject(hdc, old_hfont);
+    ReleaseDC(edit, hdc);
+}
+
+/// Submits the reply when enter is pressed in the reply input, and dismisses the notification on escape.
+unsafe extern "system" fn reply_input_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _id: usize,
+    _data: usize,
+) -> LRESULT {
+    match msg {
+        w32wm::WM_KEYDOWN if wparam == VK_RETURN as WPARAM => {
+            PostMessageW(GetParent(hwnd), WM_SUBMIT_REPLY, 0, 0);
+            0
+        }
+        w32wm::WM_KEYDOWN if wparam == VK_ESCAPE as WPARAM => {
+            PostMessageW(
+                GetParent(hwnd),
+                WM_CLOSE,
+                CloseReason::Dismissed.to_wparam(),
+                0,
+            );
+            0
+        }
+        // a single line edit control beeps on these
+        w32wm::WM_CHAR if wparam == VK_RETURN as WPARAM || wparam == VK_ESCAPE as WPARAM => 0,
+        w32wm::WM_PAINT => {
+            let result = DefSubclassProc(hwnd, msg, wparam, lparam);
+            paint_reply_placeholder(hwnd);
+            result
+        }
+        w32wm::WM_NCDESTROY => {
+            RemoveWindowSubclass(hwnd, Some(reply_input_proc), REPLY_INPUT_ID);
+            DefSubclassProc(hwnd, msg, wparam, lparam)
+        }
+        _ => DefSubclassProc(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// Shows `notification` in the window of a notification with the same tag, see [`Notification::tag`].
+unsafe fn replace_notification(hwnd: HWND, userdata: *mut WindowData, notification: Notification) {
+    // a sound read from the memory of the replaced notification must not outlive it
+    stop_sound(hwnd, &(*userdata).notification);
+
+    // the callbacks set on the new notification replace the previous ones,
+    // a close callback replaced is called so it still runs exactly once
+    if let Some(f) = notification.on_click.take() {
+        (*userdata).on_click = Some(f);
+    }
+    if let Some(f) = notification.on_action.take() {
+        (*userdata).on_action = Some(f);
+    }
+    if let Some(f) = notification.on_close.take() {
+        if let Some(on_close) = (*userdata).on_close.replace(f) {
+            on_close(CloseReason::Replaced);
+        }
+    }
+    if let Some(f) = notification.on_dismiss.take() {
+        (*userdata).on_dismiss = Some(f);
+    }
+    if let Some(f) = notification.on_reply.take() {
+        (*userdata).on_reply = Some(f);
+    }
+    (*userdata).notification = notification;
+    (*userdata).hovered_action = None;
+    (*userdata).pressed_action = None;
+
+    let edit = (*userdata).reply_input;
+    match &(*userdata).notification.reply_placeholder {
+        Some(_) if edit == 0 => create_reply_input(hwnd, userdata),
+        Some(_) => {
+            SetWindowTextW(edit, w!(""));
+            // the new placeholder is drawn
+            InvalidateRect(edit, std::ptr::null(), 1);
+        }
+        None if edit != 0 => {
+            DestroyWindow(edit);
+            (*userdata).reply_input = 0;
+            (*userdata).reply_focused = false;
+        }
+        None => {}
+    }
+
+    // the timeout starts over
+    let notification = &(*userdata).notification;
+    update_progress_timer(hwnd, notification.progress.as_ref());
+    (*userdata).countdown = Countdown::start(
+        notification.urgency.timeout(notification.timeout),
+        Instant::now(),
+    );
+    KillTimer(hwnd, TIMEOUT_TIMER_ID);
+    if (*userdata).mouse_inside || (*userdata).reply_focused {
+        (*userdata).pause_countdown();
+    } else if let Some(countdown) = (*userdata).countdown {
+        set_timer(hwnd, TIMEOUT_TIMER_ID, countdown.remaining());
+    }
+
+    if !notification.silent_replace {
+        play_sound(hwnd, notification);
+    }
+
+    // picks up the theme of the new notification and resizes it for its content
+    refresh_theme(hwnd, userdata);
+}
+
+/// Replaces the notification of `handle` with `notification`, returns `false` if it is closed.
+fn replace_shown(notification: &Notification, handle: &NotificationHandle) -> bool {
+    let mut active_noti = match ACTIVE_NOTIFICATIONS.lock() {
+        Ok(active_noti) => active_noti,
+        Err(_) => return false,
+    };
+    match active_noti.iter_mut().find(|n| n.handle.same(handle)) {
+        Some(active) if handle.replace(notification) => {
+            active.urgency = notification.urgency;
+            active.appname = notification.appname.clone();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Closes the active and queued notifications of `group`, see [`Notification::group`].
+pub(crate) fn remove_group(group: &str) {
+    let in_group = |n: &Notification| n.group.as_deref() == Some(group);
+    let queued = QUEUE
+        .lock()
+        .map(|mut queue| queue.remove_where(|q| in_group(&q.notification)))
+        .unwrap_or_default();
+    for queued in queued {
+        queued.cancel(CloseReason::Closed);
+    }
+
+    if let Ok(active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        for notification in active_noti.iter() {
+            if notification.group.as_deref() == Some(group) {
+                notification.handle.close();
+            }
+        }
+    }
+}
+
+/// Returns whether a notification of `size` can be shown on `monitor` now,
+/// see [`NotificationManager::set_max_visible`](crate::NotificationManager::set_max_visible).
+unsafe fn has_room(
+    monitor: HMONITOR,
+    work_area: RECT,
+    size: (i32, i32),
+    settings: &manager::Settings,
+) -> bool {
+    let mut sizes = match ACTIVE_NOTIFICATIONS.lock() {
+        Ok(active_noti) => active_noti
+            .iter()
+            .zip(collapse_states(&active_noti))
+            .filter(|(n, collapse)| n.monitor == monitor && *collapse != Collapse::Hidden)
+            .map(|(n, _)| util::window_size(n.hwnd))
+            .collect::<Vec<_>>(),
+        Err(_) => return true,
+    };
+    // a notification is shown even if it doesn't fit alone
+    match settings.max_visible {
+        Some(max_visible) => sizes.len() < max_visible.max(1),
+        None => {
+            sizes.push(size);
+            sizes.len() == 1 || layout::fits(work_area, settings.stack_direction, &sizes)
+        }
+    }
+}
+
+/// Asks the thread that showed the next queued notification to show it,
+/// and the following ones while there is room for them.
+pub(crate) fn show_queued() {
+    loop {
+        let thread_window = match QUEUE.lock() {
+            Ok(queue) => match queue.peek() {
+                Some(next) => next.thread_window,
+                None => return,
+            },
+            Err(_) => return,
+        };
+        if unsafe { PostMessageW(thread_window, WM_SHOW_QUEUED, 0, 0) } != 0 {
+            return;
+        }
+
+        // the thread is gone, its notifications can't be shown anymore
+        let orphans = QUEUE
+            .lock()
+            .map(|mut queue| queue.remove_where(|q| q.thread_window == thread_window))
+            .unwrap_or_default();
+        for orphan in orphans {
+            orphan.cancel(CloseReason::Closed);
+        }
+    }
+}
+
+/// Shows the queued notifications of the thread of `thread_window` while there is room for them,
+/// unless they are held back for the quiet time.
+unsafe fn show_queued_on(thread_window: HWND) {
+    let quiet = manager::settings().quiet;
+    let is_quiet = manager::is_quiet(&quiet);
+    // the mode may have changed since the notifications were held back
+    if is_quiet {
+        let suppressed = QUEUE
+            .lock()
+            .map(|mut queue| {
+                queue.remove_where(|q| {
+                    quiet.admit(q.notification.urgency, true) == Admission::Suppress
+                })
+            })
+            .unwrap_or_default();
+        for queued in suppressed {
+            queued.cancel(CloseReason::Suppressed);
+        }
+    }
+
+    loop {
+        let next = match QUEUE.lock() {
+            Ok(mut queue) => match queue.peek() {
+                // held back until the quiet time ends
+                Some(next)
+                    if quiet.admit(next.notification.urgency, is_quiet) != Admission::Show =>
+                {
+                    return
+                }
+                Some(next) if next.thread_window == thread_window => queue.pop(),
+                Some(_) => None,
+                None => return,
+            },
+            Err(_) => return,
+        };
+        let next = match next {
+            Some(next) => next,
+            // the next one is shown by its own thread
+            None => {
+                show_queued();
+                return;
+            }
+        };
+        if next.replaces && replace_shown(&next.notification, &next.handle) {
+            continue;
+        }
+        // the notification it replaces closed meanwhile, the replacement is shown anew
+        let handle = if next.replaces {
+            NotificationHandle::new()
+        } else {
+            next.handle.clone()
+        };
+        match next.notification.show_window(&handle, true) {
+            Ok(true) => {}
+            // still no room, it is back in the queue
+            Ok(false) => return,
+            Err(_) => next.cancel(CloseReason::Closed),
+        }
+    }
+}
+
+/// Returns the message-only window of the current thread, created on first use,
+/// queued notifications are shown on the thread that showed them through it.
+unsafe fn queue_window() -> HWND {
+    QUEUE_WINDOW.with(|window| {
+        if window.get() == 0 {
+            let hinstance = GetModuleHandleW(ptr::null());
+            let class_name = w!("win7-notifications-queue");
             let wnd_class = WNDCLASSEXW {
-                lpfnWndProc: Some(window_proc),
+                lpfnWndProc: Some(queue_window_proc),
                 lpszClassName: class_name,
                 hInstance: hinstance,
-                hbrBackground: CreateSolidBrush(WC),
+                hbrBackground: 0,
                 cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
-                style: CS_HREDRAW | CS_VREDRAW | CS_OWNDC,
+                style: 0,
                 cbClsExtra: 0,
                 cbWndExtra: 0,
                 hIcon: 0,
-                hCursor: 0, // must be null in order for cursor state to work properly
+                hCursor: 0,
                 lpszMenuName: ptr::null(),
                 hIconSm: 0,
             };
+            // the class is registered only once per process, the later calls fail
             RegisterClassExW(&wnd_class);
+            window.set(CreateWindowExW(
+                0,
+                class_name,
+                ptr::null(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                0,
+                hinstance,
+                ptr::null(),
+            ));
+        }
+        window.get()
+    })
+}
 
-            if let Ok(pm) = PRIMARY_MONITOR.lock() {
-                let RECT { right, bottom, .. } = pm.monitorInfo.rcWork;
+unsafe extern "system" fn queue_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_SHOW_QUEUED => {
+            show_queued_on(hwnd);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
 
-                let data = WindowData {
-                    window: 0,
-                    mouse_hovering_close_btn: false,
-                    notification: self.clone(),
-                };
+/// Returns whether the queued notifications wait for the quiet time to end.
+fn is_holding_queue() -> bool {
+    let quiet = manager::settings().quiet;
+    quiet.mode == QuietMode::Queue && manager::is_quiet(&quiet)
+}
 
-                let hwnd = CreateWindowExW(
-                    WS_EX_TOPMOST,
-                    class_name,
-                    w!("win7-notifications-window"),
-                    WS_SYSMENU | WS_CAPTION | WS_VISIBLE,
-                    right - NW - 15,
-                    bottom - NH - 15,
-                    NW,
-                    NH,
-                    0,
-                    0,
-                    hinstance,
-                    Box::into_raw(Box::new(data)) as _,
-                );
+/// Checks regularly on the current thread whether the quiet time ended, to show the notifications held back.
+unsafe fn start_quiet_timer() {
+    QUIET_TIMER.with(|timer| {
+        if timer.get() == 0 {
+            let elapse = QUIET_CHECK_INTERVAL.as_millis() as u32;
+            timer.set(SetTimer(0, 0, elapse, Some(quiet_timer_proc)));
+        }
+    });
+}
 
-                if hwnd == 0 {
-                    return Err(GetLastError());
-                }
+unsafe extern "system" fn quiet_timer_proc(_hwnd: HWND, _msg: u32, id: usize, _time: u32) {
+    if !is_holding_queue() {
+        KillTimer(0, id);
+        QUIET_TIMER.with(|timer| timer.set(0));
+        show_queued();
+    }
+}
 
-                // reposition active notifications and make room for new one
-                if let Ok(mut active_notifications) = ACTIVE_NOTIFICATIONS.lock() {
-                    active_notifications.push(hwnd);
-                    let mut i = active_notifications.len() as i32;
-                    for hwnd in active_notifications.iter() {
-                        SetWindowPos(
-                            *hwnd,
-                            0,
-                            right - NW - 15,
-                            bottom - 15 - (NH * i) - 10 * (i - 1),
-                            0,
-                            0,
-                            SWP_NOACTIVATE | SWP_NOSIZE | SWP_NOZORDER,
-                        );
-                        i -= 1;
-                    }
-                }
+/// Removes a notification from the queue before it is shown, or the replacement held back for it,
+/// returns `false` if there is none.
+pub(crate) fn cancel_queued(handle: &NotificationHandle) -> bool {
+    let queued = QUEUE
+        .lock()
+        .map(|mut queue| queue.remove_where(|q| q.handle.same(handle)))
+        .unwrap_or_default();
+    let cancelled = !queued.is_empty();
+    for queued in queued {
+        queued.cancel(CloseReason::Closed);
+    }
+    cancelled
+}
 
-                // shadows
-                let mut is_dwm_enabled = 0;
-                DwmIsCompositionEnabled(&mut is_dwm_enabled);
-                if is_dwm_enabled == 1 {
-                    let margins = MARGINS {
-                        cxLeftWidth: 1,
-                        cxRightWidth: 0,
-                        cyBottomHeight: 0,
-                        cyTopHeight: 0,
-                    };
-                    DwmExtendFrameIntoClientArea(hwnd, &margins);
-                }
+/// Applies an update to a notification waiting in the queue, or to the replacement held back for it,
+/// returns `false` if there is none.
+pub(crate) fn update_queued(handle: &NotificationHandle, notification: &Notification) -> bool {
+    let mut queue = match QUEUE.lock() {
+        Ok(queue) => queue,
+        Err(_) => return false,
+    };
+    match queue.find_mut(|q| q.handle.same(handle)) {
+        Some(queued) => {
+            queued.notification.update_content(notification.clone());
+            true
+        }
+        None => false,
+    }
+}
 
-                util::skip_taskbar(hwnd);
-                ShowWindow(hwnd, SW_SHOW);
-                if !self.silent {
-                    // Passing an invalid path to `PlaySoundW` will make windows play default sound.
-                    // https://docs.microsoft.com/en-us/previous-versions/dd743680(v=vs.85)#remarks
-                    PlaySoundW(w!("null"), hinstance, SND_ASYNC);
+/// Forgets the tag of a closing notification, a notification shown with the same tag is shown anew.
+fn untag_notification(hwnd: HWND) {
+    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        if let Some(notification) = active_noti.iter_mut().find(|n| n.hwnd == hwnd) {
+            notification.tag = None;
+        }
+    }
+}
+
+/// Closes a notification from the thread of its window,
+/// other threads post a `WM_CLOSE` as the window may be destroyed meanwhile.
+unsafe fn close_notification(hwnd: HWND, reason: CloseReason) {
+    // an animated notification stays visible while it fades out
+    if !animations_enabled() {
+        ShowWindow(hwnd, SW_HIDE);
+        CloseWindow(hwnd);
+    }
+
+    // We can NOT call `DestroyWindow` from this window
+    // Sending WM_CLOSE will by default make the windows call it on itself.
+    // Note WM_DESTROY should not be sent directly as it would create a leak
+    // see https://devblogs.microsoft.com/oldnewthing/20110926-00/?p=9553
+    SendMessageA(hwnd, WM_CLOSE, reason.to_wparam(), 0);
+}
+
+/// Removes a destroyed notification from the active notifications and repositions the remaining ones,
+/// queued notifications take the room it leaves.
+unsafe fn remove_notification(hwnd: HWND, reason: CloseReason) {
+    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        if let Some(index) = active_noti.iter().position(|e| e.hwnd == hwnd) {
+            if matches!(reason, CloseReason::Dismissed | CloseReason::Clicked) {
+                dismiss_collapsed(&active_noti, index);
+            }
+            active_noti.remove(index);
+        }
+
+        // reposition notifications
+        reposition_notifications(&mut active_noti);
+    }
+    show_queued();
+}
+
+/// Repositions the active notifications, used when the placement settings change.
+pub(crate) fn reposition_active_notifications() {
+    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        unsafe { reposition_notifications(&mut active_noti) };
+    }
+}
+
+/// Moves the active notifications to the nearest monitor still available
+/// and restacks them in its new work area, used when the display settings change.
+unsafe fn refresh_monitors() {
+    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        for notification in active_noti.iter_mut() {
+            notification.monitor = MonitorFromWindow(notification.hwnd, MONITOR_DEFAULTTONEAREST);
+        }
+        reposition_notifications(&mut active_noti);
+    }
+}
+
+/// Stacks the active notifications on their monitor, the newest one being the last.
+unsafe fn reposition_notifications(active_notifications: &mut [ActiveNotification]) {
+    let settings = manager::settings();
+
+    // the collapsed notifications are hidden and left out of the stack
+    let collapse = collapse_states(active_notifications);
+    for (notification, collapse) in active_notifications.iter_mut().zip(&collapse) {
+        let (more, hidden) = match collapse {
+            Collapse::Shown => (0, false),
+            Collapse::Summary { more } => (*more, false),
+            Collapse::Hidden => (0, true),
+        };
+        notification.hidden = hidden;
+        PostMessageW(
+            notification.hwnd,
+            WM_COLLAPSE_NOTIFICATION,
+            more,
+            hidden as LPARAM,
+        );
+    }
+
+    let mut monitors: Vec<HMONITOR> = Vec::new();
+    for notification in active_notifications.iter() {
+        if !monitors.contains(&notification.monitor) {
+            monitors.push(notification.monitor);
+        }
+    }
+
+    for monitor in monitors {
+        let monitor_info = match util::get_monitor_info(monitor) {
+            Ok(info) => info.monitorInfo,
+            Err(_) => continue,
+        };
+
+        let mut stack = active_notifications
+            .iter()
+            .zip(&collapse)
+            .rev()
+            .filter(|(n, collapse)| n.monitor == monitor && **collapse != Collapse::Hidden)
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+        // critical notifications take the positions closest to the corner
+        stack.sort_by_key(|n| n.urgency != Urgency::Critical);
+        let stack = stack.into_iter().map(|n| n.hwnd).collect::<Vec<_>>();
+        let sizes = stack
+            .iter()
+            .map(|hwnd| util::window_size(*hwnd))
+            .collect::<Vec<_>>();
+        let rects = layout::layout(
+            monitor_info.rcWork,
+            monitor_info.rcMonitor,
+            settings.position,
+            settings.stack_direction,
+            &sizes,
+        );
+        for (hwnd, rect) in stack.into_iter().zip(rects) {
+            // each notification moves itself on its own thread, gliding to its new position if animated
+            PostMessageW(
+                hwnd,
+                WM_MOVE_NOTIFICATION,
+                rect.left as WPARAM,
+                rect.top as LPARAM,
+            );
+        }
+    }
+}
+
+/// Returns how each active notification is shown, see
+/// [`NotificationManager::set_collapse_bursts`](crate::NotificationManager::set_collapse_bursts).
+fn collapse_states(active_notifications: &[ActiveNotification]) -> Vec<Collapse> {
+    if !manager::settings().collapse_bursts {
+        return vec![Collapse::Shown; active_notifications.len()];
+    }
+    let arrivals = active_notifications
+        .iter()
+        .map(ActiveNotification::arrival)
+        .collect::<Vec<_>>();
+    burst::collapse(&arrivals, BURST_MIN_COUNT, BURST_GAP)
+}
+
+/// Dismisses the notifications hidden behind the one at `index` if it stands for a collapsed burst,
+/// used when the user closes it.
+unsafe fn dismiss_collapsed(active_notifications: &[ActiveNotification], index: usize) {
+    let collapse = collapse_states(active_notifications);
+    if !matches!(collapse[index], Collapse::Summary { .. }) {
+        return;
+    }
+    let arrivals = active_notifications
+        .iter()
+        .map(ActiveNotification::arrival)
+        .collect::<Vec<_>>();
+    let bursts = burst::bursts(&arrivals, BURST_GAP);
+    for (i, notification) in active_notifications.iter().enumerate() {
+        if bursts[i] == bursts[index] && collapse[i] == Collapse::Hidden {
+            PostMessageW(
+                notification.hwnd,
+                WM_CLOSE,
+                CloseReason::Dismissed.to_wparam(),
+                0,
+            );
+        }
+    }
+}
+
+/// Shows all the notifications collapsed with the one in `hwnd`.
+unsafe fn expand_burst(hwnd: HWND) {
+    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        let arrivals = active_noti
+            .iter()
+            .map(ActiveNotification::arrival)
+            .collect::<Vec<_>>();
+        let bursts = burst::bursts(&arrivals, BURST_GAP);
+        if let Some(index) = active_noti.iter().position(|n| n.hwnd == hwnd) {
+            for (notification, burst) in active_noti.iter_mut().zip(&bursts) {
+                if *burst == bursts[index] {
+                    notification.expanded = true;
                 }
+            }
+        }
+        reposition_notifications(&mut active_noti);
+    }
+}
+
+/// Makes the active notifications pick up the theme and the layout settings of the [`NotificationManager`](crate::NotificationManager).
+pub(crate) fn refresh_active_themes() {
+    if let Ok(active_noti) = ACTIVE_NOTIFICATIONS.lock() {
+        for notification in active_noti.iter() {
+            unsafe { PostMessageW(notification.hwnd, WM_REFRESH_THEME, 0, 0) };
+        }
+    }
+}
+
+/// Returns the theme of the notification, its own or the one of the [`NotificationManager`](crate::NotificationManager).
+fn resolve_theme(notification: &Notification) -> Theme {
+    match &notification.theme {
+        Some(theme) => theme.clone(),
+        None => manager::settings()
+            .theme_mode
+            .resolve(&util::Win32SystemSettings),
+    }
+}
+
+/// Picks up the current theme, resizing the notification if its sizes changed.
+unsafe fn refresh_theme(hwnd: HWND, userdata: *mut WindowData) {
+    (*userdata).theme = resolve_theme(&(*userdata).notification);
+    let metrics = Metrics::new((*userdata).metrics.dpi, &(*userdata).theme);
+    apply_metrics(hwnd, userdata, metrics);
+}
+
+/// Resizes the notification for new metrics and repaints it.
+unsafe fn apply_metrics(hwnd: HWND, userdata: *mut WindowData, metrics: Metrics) {
+    (*userdata).metrics = metrics;
+    (*userdata).update_layout();
+    (*userdata).update_reply_font();
+    let width = metrics.width;
+    let height = (*userdata).layout.height;
+    SetWindowPos(
+        hwnd,
+        0,
+        0,
+        0,
+        width,
+        height,
+        SWP_NOACTIVATE | SWP_NOMOVE | SWP_NOZORDER,
+    );
+    set_corner_region(hwnd, metrics, width, height);
+    InvalidateRect(hwnd, std::ptr::null(), 1);
+
+    // this may happen while the notifications are being repositioned
+    PostMessageW(hwnd, WM_REPOSITION_NOTIFICATIONS, 0, 0);
+}
+
+/// Clips the corners of the notification to the corner radius of its theme.
+unsafe fn set_corner_region(hwnd: HWND, metrics: Metrics, width: i32, height: i32) {
+    let region = if metrics.corner_radius > 0 {
+        let diameter = metrics.corner_radius * 2;
+        CreateRoundRectRgn(0, 0, width + 1, height + 1, diameter, diameter)
+    } else {
+        0
+    };
+    // the system owns the region from now on
+    SetWindowRgn(hwnd, region, 1);
+}
+
+/// Returns whether notifications are animated,
+/// they aren't when animations are disabled in the [`NotificationManager`](crate::NotificationManager)
+/// or client area animations are turned off in the system settings.
+unsafe fn animations_enabled() -> bool {
+    if !manager::settings().animations {
+        return false;
+    }
+    let mut enabled: BOOL = 1;
+    SystemParametersInfoW(
+        SPI_GETCLIENTAREAANIMATION,
+        0,
+        &mut enabled as *mut BOOL as _,
+        0,
+    );
+    enabled != 0
+}
+
+unsafe fn start_animation_timer(hwnd: HWND) {
+    set_timer(hwnd, ANIMATION_TIMER_ID, FRAME_INTERVAL);
+}
+
+/// Animates the progress bar while it is indeterminate.
+unsafe fn update_progress_timer(hwnd: HWND, progress: Option<&Progress>) {
+    if progress.map_or(false, Progress::is_indeterminate) {
+        set_timer(hwnd, PROGRESS_TIMER_ID, FRAME_INTERVAL);
+    } else {
+        KillTimer(hwnd, PROGRESS_TIMER_ID);
+    }
+}
+
+/// Starts or restarts the timer `id` of the window.
+unsafe fn set_timer(hwnd: HWND, id: usize, elapse: Duration) {
+    SetTimer(hwnd, id, elapse.as_millis() as u32, None);
+}
+
+type ClickCallback = dyn FnMut() + Send;
+type ActionCallback = dyn FnMut(&str) + Send;
+type CloseCallback = dyn FnOnce(CloseReason) + Send;
+type ReplyCallback = dyn FnMut(&str) + Send;
+
+/// A callback slot of a [`Notification`], shared between clones of the builder
+/// until it is moved into a notification window by [`Notification::show`].
+struct Callback<F: ?Sized>(Arc<Mutex<Option<Box<F>>>>);
+
+impl<F: ?Sized> Callback<F> {
+    fn new(f: Box<F>) -> Self {
+        Callback(Arc::new(Mutex::new(Some(f))))
+    }
+
+    fn take(&self) -> Option<Box<F>> {
+        self.0.lock().ok().and_then(|mut f| f.take())
+    }
+}
+
+impl<F: ?Sized> Default for Callback<F> {
+    fn default() -> Self {
+        Callback(Arc::new(Mutex::new(None)))
+    }
+}
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Callback(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let is_set = self.0.lock().map(|f| f.is_some()).unwrap_or(false);
+        f.debug_tuple("Callback").field(&is_set).finish()
+    }
+}
+
+struct WindowData {
+    window: HWND,
+    notification: Notification,
+    theme: Theme,
+    /// Sizes for the theme and the DPI of the monitor the notification is on.
+    metrics: Metrics,
+    /// The parts of the notification for its current content and metrics.
+    layout: Layout,
+    text: Text,
+    mouse_hovering_close_btn: bool,
+    /// Set while the mouse is over the notification, a `WM_MOUSELEAVE` is then requested.
+    mouse_inside: bool,
+    hovered_action: Option<usize>,
+    pressed_action: Option<usize>,
+    on_click: Option<Box<ClickCallback>>,
+    on_action: Option<Box<ActionCallback>>,
+    on_close: Option<Box<CloseCallback>>,
+    on_dismiss: Option<Box<CloseCallback>>,
+    on_reply: Option<Box<ReplyCallback>>,
+    /// The reason of the first close request, callbacks are fired with it on `WM_DESTROY`.
+    close_reason: Option<CloseReason>,
+    handle: NotificationHandle,
+    movement: Option<Movement>,
+    fade: Option<Fade>,
+    /// The current opacity of the layered window.
+    alpha: u8,
+    /// Set once the notification started fading out, the window is destroyed when the fade ends.
+    closing: bool,
+    /// `None` if the notification never expires.
+    countdown: Option<Countdown>,
+    /// The start of the indeterminate progress bar animation.
+    shown_at: Instant,
+    /// The images scaled to the current layout, prepared on the first paint.
+    scaled_images: Option<ScaledImages>,
+    /// The edit control of the reply input, `0` without one.
+    reply_input: HWND,
+    /// The font of the reply input, recreated when the metrics change.
+    reply_font: HFONT,
+    /// Set while the reply input has the keyboard focus, the notification doesn't expire meanwhile.
+    reply_focused: bool,
+    /// How many collapsed notifications this one stands for.
+    more: usize,
+}
+
+/// The text of a notification, wrapped and cut to the room it has.
+struct Text {
+    summary: String,
+    body: Vec<String>,
+    body_line_height: i32,
+}
+
+/// The images of a notification, scaled to the size they are drawn at.
+struct ScaledImages {
+    icon: Option<Image>,
+    hero: Option<Image>,
+    logo: Option<Image>,
+}
 
-                let timeout = self.timeout;
-                thread::spawn(move || {
-                    thread::sleep(Duration::from_millis(timeout.into()));
-                    if timeout != Timeout::Never {
-                        close_notification(hwnd);
-                    };
-                });
+impl ScaledImages {
+    fn new(notification: &Notification, metrics: &Metrics, layout: &Layout) -> ScaledImages {
+        let icon_size = metrics.icon_size as u32;
+        let icon = notification.icon.as_ref().and_then(|icon| {
+            Image::from_rgba(
+                icon.clone(),
+                notification.icon_width,
+                notification.icon_height,
+            )
+            .ok()
+            .map(|icon| icon.resize(icon_size, icon_size))
+        });
+        let hero = notification
+            .image
+            .as_ref()
+            .zip(layout.hero)
+            .map(|(image, rc)| {
+                image.resize_to_fill((rc.right - rc.left) as u32, (rc.bottom - rc.top) as u32)
+            });
+        let logo = notification.app_logo_override.as_ref().map(|logo| {
+            let size = metrics.logo_size as u32;
+            let mut logo = logo.resize_to_fill(size, size);
+            if notification.app_logo_crop == LogoCrop::Circle {
+                logo.mask_circle();
             }
-        }
-
-        Ok(())
+            logo
+        });
+        ScaledImages { icon, hero, logo }
     }
 }
 
-unsafe fn close_notification(hwnd: HWND) {
-    ShowWindow(hwnd, SW_HIDE);
-    CloseWindow(hwnd);
+/// A running animation of the notification position.
+#[derive(Debug, Copy, Clone)]
+struct Movement {
+    started: Instant,
+    x: Tween,
+    y: Tween,
+}
 
-    // We can NOT call `DestroyWindow` from this window
-    // Sending WM_CLOSE will by default make the windows call it on itself.
-    // Note WM_DESTROY should not be sent directly as it would create a leak
-    // see https://devblogs.microsoft.com/oldnewthing/20110926-00/?p=9553
-    SendMessageA(hwnd, WM_CLOSE, 0, 0);
+/// A running animation of the notification opacity.
+#[derive(Debug, Copy, Clone)]
+struct Fade {
+    started: Instant,
+    alpha: Tween,
+}
 
-    if let Ok(mut active_noti) = ACTIVE_NOTIFICATIONS.lock() {
-        if let Some(index) = active_noti.iter().position(|e| *e == hwnd) {
-            active_noti.remove(index);
+impl WindowData {
+    /// Lays the notification out again after its content or metrics changed.
+    unsafe fn update_layout(&mut self) {
+        let (layout, text) = self
+            .notification
+            .layout(&self.theme, &self.metrics, self.more);
+        self.layout = layout;
+        self.text = text;
+        self.scaled_images = None;
+        self.place_reply_input();
+    }
+
+    /// Moves the reply input to its place in the layout, its text vertically centered.
+    unsafe fn place_reply_input(&self) {
+        if self.reply_input == 0 {
+            return;
+        }
+        if let Some(rc) = self.layout.reply {
+            let padding = self.metrics.margin / 2;
+            let height = self.text.body_line_height;
+            SetWindowPos(
+                self.reply_input,
+                0,
+                rc.left + padding,
+                rc.top + (rc.bottom - rc.top - height) / 2,
+                rc.right - rc.left - padding * 2,
+                height,
+                SWP_NOACTIVATE | SWP_NOZORDER,
+            );
+        }
+    }
+
+    /// Sets the font of the reply input for the current theme and metrics.
+    unsafe fn update_reply_font(&mut self) {
+        if self.reply_input == 0 {
+            return;
         }
+        let font = util::create_font(
+            &self.theme.font_family,
+            self.metrics.body_font_size,
+            self.theme.body_font_weight,
+        );
+        SendMessageW(self.reply_input, WM_SETFONT, font as WPARAM, 1);
+        if self.reply_font != 0 {
+            DeleteObject(self.reply_font);
+        }
+        self.reply_font = font;
+    }
 
-        // reposition notifications
-        if let Ok(pm) = PRIMARY_MONITOR.lock() {
-            let RECT { right, bottom, .. } = pm.monitorInfo.rcWork;
-            for (i, h) in active_noti.iter().rev().enumerate() {
-                SetWindowPos(
-                    *h,
-                    0,
-                    right - NW - 15,
-                    bottom - (NH * (i + 1) as i32) - 15,
-                    0,
-                    0,
-                    SWP_NOSIZE | SWP_NOZORDER,
-                );
-            }
+    /// Stops the timeout while the user interacts with the notification.
+    unsafe fn pause_countdown(&mut self) {
+        if let Some(countdown) = &mut self.countdown {
+            countdown.pause(Instant::now());
+            KillTimer(self.window, TIMEOUT_TIMER_ID);
         }
     }
-}
 
-struct WindowData {
-    window: HWND,
-    notification: Notification,
-    mouse_hovering_close_btn: bool,
+    /// Resumes the timeout once the mouse left the notification and the reply input lost the focus.
+    unsafe fn resume_countdown(&mut self) {
+        if self.mouse_inside || self.reply_focused {
+            return;
+        }
+        if let Some(countdown) = &mut self.countdown {
+            let remaining = countdown.resume(Instant::now());
+            set_timer(self.window, TIMEOUT_TIMER_ID, remaining);
+        }
+    }
+
+    /// Returns whether the "+N more" line is under the given point.
+    fn more_at(&self, x: i32, y: i32) -> bool {
+        self.layout
+            .more
+            .map_or(false, |rc| util::rect_contains(rc, x, y))
+    }
+
+    /// Returns the index of the action button under the given point.
+    fn action_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.layout
+            .actions
+            .iter()
+            .position(|rc| util::rect_contains(*rc, x, y))
+    }
 }
 
 pub unsafe extern "system" fn window_proc(
@@ -316,12 +1892,34 @@ pub unsafe extern "system" fn window_proc(
         w32wm::WM_CREATE => {
             let userdata = userdata as *mut WindowData;
             (*userdata).window = hwnd;
+            (*userdata).handle.set_window(hwnd);
+            create_reply_input(hwnd, userdata);
             DefWindowProcW(hwnd, msg, wparam, lparam)
         }
 
+        w32wm::WM_ERASEBKGND => {
+            let userdata = userdata as *mut WindowData;
+            let hdc = wparam as HDC;
+            let mut rc: RECT = std::mem::zeroed();
+            GetClientRect(hwnd, &mut rc);
+            let theme = theme::paint_theme(&(*userdata).theme, &util::Win32SystemSettings);
+            let hbrush = CreateSolidBrush(theme.background.to_colorref());
+            FillRect(hdc, &rc, hbrush);
+            DeleteObject(hbrush);
+            1
+        }
+
         w32wm::WM_PAINT => {
             let userdata = userdata as *mut WindowData;
             let notification = &(*userdata).notification;
+            // derived from the system colors when high contrast is on
+            let theme = &theme::paint_theme(&(*userdata).theme, &util::Win32SystemSettings);
+            let m = (*userdata).metrics;
+            let layout = &(*userdata).layout;
+            let text = &(*userdata).text;
+            let images = (*userdata)
+                .scaled_images
+                .get_or_insert_with(|| ScaledImages::new(notification, &m, layout));
             let mut ps = PAINTSTRUCT {
                 fErase: 0,
                 fIncUpdate: 0,
@@ -336,47 +1934,71 @@ pub unsafe extern "system" fn window_proc(
                 rgbReserved: [0; 32],
             };
             let hdc = BeginPaint(hwnd, &mut ps);
-            SetBkColor(hdc, WC);
+            SetBkColor(hdc, theme.background.to_colorref());
+
+            // draw the accent stripe of critical notifications
+            if notification.urgency == Urgency::Critical {
+                let rc = RECT {
+                    left: 0,
+                    top: 0,
+                    right: m.critical_stripe_width,
+                    bottom: layout.height,
+                };
+                let hbrush = CreateSolidBrush(theme.critical_color.to_colorref());
+                FillRect(hdc, &rc, hbrush);
+                DeleteObject(hbrush);
+            }
+
+            // draw the box around the reply input
+            if let Some(rc) = layout.reply {
+                let hbrush = CreateSolidBrush(theme.action_button_color.to_colorref());
+                FillRect(hdc, &rc, hbrush);
+                DeleteObject(hbrush);
+            }
 
             // draw notification icon
-            {
-                if let Some(icon) = &notification.icon {
-                    let hicon = util::get_hicon_from_32bpp_rgba(
-                        icon.clone(),
-                        notification.icon_width,
-                        notification.icon_height,
-                    );
-                    DrawIconEx(hdc, NM, NM, hicon, NIS, NIS, 0, 0, DI_NORMAL);
-                }
+            if let Some(icon) = &images.icon {
+                util::draw_image(hdc, icon, m.margin, m.margin);
+            }
+
+            // draw notification hero image
+            if let (Some(hero), Some(rc)) = (&images.hero, layout.hero) {
+                util::draw_image(hdc, hero, rc.left, rc.top);
+            }
+
+            // draw notification app logo
+            if let (Some(logo), Some(rc)) = (&images.logo, layout.logo) {
+                util::draw_image(hdc, logo, rc.left, rc.top);
             }
 
             // draw notification close button
             {
+                let close_btn_rect = m.close_btn_rect();
                 let hpen = CreatePen(
                     PS_SOLID,
-                    2,
+                    m.close_btn_pen_width,
                     if (*userdata).mouse_hovering_close_btn {
-                        TC
+                        theme.close_button_hover_color.to_colorref()
                     } else {
-                        SC
+                        theme.close_button_color.to_colorref()
                     },
                 );
                 let old_hpen = SelectObject(hdc, hpen);
 
                 MoveToEx(
                     hdc,
-                    CLOSE_BTN_RECT.left,
-                    CLOSE_BTN_RECT.top,
+                    close_btn_rect.left,
+                    close_btn_rect.top,
                     std::ptr::null_mut(),
                 );
-                LineTo(hdc, CLOSE_BTN_RECT.right, CLOSE_BTN_RECT.bottom);
+                LineTo(hdc, close_btn_rect.right, close_btn_rect.bottom);
                 MoveToEx(
                     hdc,
-                    CLOSE_BTN_RECT.right,
-                    CLOSE_BTN_RECT.top,
+                    close_btn_rect.right,
+                    close_btn_rect.top,
                     std::ptr::null_mut(),
                 );
-                LineTo(hdc, CLOSE_BTN_RECT.left, CLOSE_BTN_RECT.bottom);
+                LineTo(hdc, close_btn_rect.left, close_btn_rect.bottom);
 
                 SelectObject(hdc, old_hpen);
                 DeleteObject(hpen);
@@ -384,13 +2006,18 @@ pub unsafe extern "system" fn window_proc(
 
             // draw notification app name
             {
-                SetTextColor(hdc, TC);
-                let (hfont, old_hfont) = util::set_font(hdc, "Segeo UI", 15, 400);
+                SetTextColor(hdc, theme.appname_color.to_colorref());
+                let (hfont, old_hfont) = util::set_font(
+                    hdc,
+                    &theme.font_family,
+                    m.appname_font_size,
+                    theme.appname_font_weight,
+                );
                 let appname = util::encode_wide(&notification.appname);
                 TextOutW(
                     hdc,
-                    NM + NIS + (NM / 2),
-                    NM,
+                    m.margin + m.icon_size + (m.margin / 2),
+                    m.margin,
                     appname.as_ptr(),
                     appname.len() as _,
                 );
@@ -400,38 +2027,164 @@ pub unsafe extern "system" fn window_proc(
 
             // draw notification summary (title)
             {
-                let (hfont, old_hfont) = util::set_font(hdc, "Segeo UI", 17, 700);
-                let summary = util::encode_wide(&notification.summary);
-                TextOutW(
+                SetTextColor(hdc, theme.title_color.to_colorref());
+                let (hfont, old_hfont) = util::set_font(
                     hdc,
-                    NM,
-                    NM + NIS + (NM / 2),
-                    summary.as_ptr(),
-                    summary.len() as _,
+                    &theme.font_family,
+                    m.summary_font_size,
+                    theme.title_font_weight,
                 );
+                let (x, y) = layout.summary_origin;
+                util::text_out(hdc, x, y, &text.summary);
                 SelectObject(hdc, old_hfont);
                 DeleteObject(hfont);
             }
 
             // draw notification body
             {
-                SetTextColor(hdc, SC);
-                let (hfont, old_hfont) = util::set_font(hdc, "Segeo UI", 17, 400);
-                let mut rc = RECT {
-                    left: NM,
-                    top: NM + NIS + (NM / 2) + 17 + (NM / 2),
-                    right: NW - NM,
-                    bottom: NH - NM,
+                SetTextColor(hdc, theme.body_color.to_colorref());
+                let (hfont, old_hfont) = util::set_font(
+                    hdc,
+                    &theme.font_family,
+                    m.body_font_size,
+                    theme.body_font_weight,
+                );
+                for (i, line) in text.body.iter().enumerate() {
+                    let y = layout.body.top + i as i32 * text.body_line_height;
+                    util::text_out(hdc, layout.body.left, y, line);
+                }
+
+                SelectObject(hdc, old_hfont);
+                DeleteObject(hfont);
+            }
+
+            // draw notification progress
+            if let (Some(progress), Some((text_rc, bar_rc))) =
+                (&notification.progress, layout.progress)
+            {
+                SetTextColor(hdc, theme.body_color.to_colorref());
+                let (hfont, old_hfont) = util::set_font(
+                    hdc,
+                    &theme.font_family,
+                    m.body_font_size,
+                    theme.body_font_weight,
+                );
+
+                // the label is drawn whole, the status is cut before it
+                let mut label = util::encode_wide(&progress.label);
+                let mut label_rc = text_rc;
+                DrawTextW(
+                    hdc,
+                    label.as_mut_ptr(),
+                    -1,
+                    &mut label_rc,
+                    DT_SINGLELINE | DT_CALCRECT,
+                );
+                let label_width = label_rc.right - label_rc.left;
+                let mut label_rc = text_rc;
+                DrawTextW(
+                    hdc,
+                    label.as_mut_ptr(),
+                    -1,
+                    &mut label_rc,
+                    DT_RIGHT | DT_SINGLELINE,
+                );
+                let mut status = util::encode_wide(&progress.status);
+                let mut status_rc = RECT {
+                    right: text_rc.right - label_width - m.margin / 2,
+                    ..text_rc
+                };
+                DrawTextW(
+                    hdc,
+                    status.as_mut_ptr(),
+                    -1,
+                    &mut status_rc,
+                    DT_LEFT | DT_SINGLELINE | DT_END_ELLIPSIS,
+                );
+                SelectObject(hdc, old_hfont);
+                DeleteObject(hfont);
+
+                let hbrush = CreateSolidBrush(theme.progress_track_color.to_colorref());
+                FillRect(hdc, &bar_rc, hbrush);
+                DeleteObject(hbrush);
+
+                let width = bar_rc.right - bar_rc.left;
+                let (left, right) = match progress.value {
+                    Some(_) => (0, progress.filled_width(width)),
+                    None => animation::indeterminate_segment(width, (*userdata).shown_at.elapsed()),
                 };
-                let mut body = util::encode_wide(&notification.body);
+                if right > left {
+                    let filled_rc = RECT {
+                        left: bar_rc.left + left,
+                        right: bar_rc.left + right,
+                        ..bar_rc
+                    };
+                    let hbrush = CreateSolidBrush(theme.progress_bar_color.to_colorref());
+                    FillRect(hdc, &filled_rc, hbrush);
+                    DeleteObject(hbrush);
+                }
+            }
+
+            // draw the line standing for the collapsed notifications
+            if let Some(mut rc) = layout.more {
+                SetTextColor(hdc, theme.appname_color.to_colorref());
+                let (hfont, old_hfont) = util::set_font(
+                    hdc,
+                    &theme.font_family,
+                    m.body_font_size,
+                    theme.body_font_weight,
+                );
+                let mut more = util::encode_wide(format!("+{} more", (*userdata).more));
                 DrawTextW(
                     hdc,
-                    body.as_mut_ptr(),
-                    body.len() as _,
+                    more.as_mut_ptr(),
+                    -1,
                     &mut rc,
-                    DT_LEFT | DT_EXTERNALLEADING | DT_WORDBREAK,
+                    DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+                );
+                SelectObject(hdc, old_hfont);
+                DeleteObject(hfont);
+            }
+
+            // draw notification actions
+            {
+                let (hfont, old_hfont) = util::set_font(
+                    hdc,
+                    &theme.font_family,
+                    m.action_font_size,
+                    theme.action_font_weight,
                 );
+                let rects = layout.actions.iter().copied();
+                for (i, (action, mut rc)) in notification.actions.iter().zip(rects).enumerate() {
+                    let (color, text_color) = if (*userdata).pressed_action == Some(i) {
+                        (
+                            theme.action_button_pressed_color,
+                            theme.action_text_hover_color,
+                        )
+                    } else if (*userdata).hovered_action == Some(i) {
+                        (
+                            theme.action_button_hover_color,
+                            theme.action_text_hover_color,
+                        )
+                    } else {
+                        (theme.action_button_color, theme.action_text_color)
+                    };
+                    let color = color.to_colorref();
+                    let hbrush = CreateSolidBrush(color);
+                    FillRect(hdc, &rc, hbrush);
+                    DeleteObject(hbrush);
 
+                    SetBkColor(hdc, color);
+                    SetTextColor(hdc, text_color.to_colorref());
+                    let mut label = util::encode_wide(&action.label);
+                    DrawTextW(
+                        hdc,
+                        label.as_mut_ptr(),
+                        label.len() as _,
+                        &mut rc,
+                        DT_CENTER | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+                    );
+                }
                 SelectObject(hdc, old_hfont);
                 DeleteObject(hfont);
             }
@@ -444,31 +2197,425 @@ pub unsafe extern "system" fn window_proc(
             let userdata = userdata as *mut WindowData;
 
             let (x, y) = (GET_X_LPARAM(lparam), GET_Y_LPARAM(lparam));
-            let hit = util::rect_contains(CLOSE_BTN_RECT_EXTRA, x as i32, y as i32);
+            let close_btn_rect = (*userdata).metrics.close_btn_hit_rect();
+            let hit = util::rect_contains(close_btn_rect, x as i32, y as i32);
+            let hovered_action = (*userdata).action_at(x as i32, y as i32);
+
+            if !(*userdata).mouse_inside {
+                (*userdata).mouse_inside = true;
+                let mut tme = TRACKMOUSEEVENT {
+                    cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: hwnd,
+                    dwHoverTime: 0,
+                };
+                TrackMouseEvent(&mut tme);
 
-            SetCursor(LoadCursorW(0, if hit { IDC_HAND } else { IDC_ARROW }));
-            if hit != (*userdata).mouse_hovering_close_btn {
+                // the notification doesn't expire while it is hovered
+                (*userdata).pause_countdown();
+            }
+
+            let hand = hit || hovered_action.is_some() || (*userdata).more_at(x as i32, y as i32);
+            SetCursor(LoadCursorW(0, if hand { IDC_HAND } else { IDC_ARROW }));
+            if hit != (*userdata).mouse_hovering_close_btn
+                || hovered_action != (*userdata).hovered_action
+            {
                 // only trigger redraw if the previous state is different than the new state
                 InvalidateRect(hwnd, std::ptr::null(), 0);
             }
             (*userdata).mouse_hovering_close_btn = hit;
+            (*userdata).hovered_action = hovered_action;
 
             DefWindowProcW(hwnd, msg, wparam, lparam)
         }
 
+        WM_MOUSELEAVE => {
+            let userdata = userdata as *mut WindowData;
+
+            (*userdata).mouse_inside = false;
+            if (*userdata).mouse_hovering_close_btn || (*userdata).hovered_action.is_some() {
+                (*userdata).mouse_hovering_close_btn = false;
+                (*userdata).hovered_action = None;
+                InvalidateRect(hwnd, std::ptr::null(), 0);
+            }
+
+            (*userdata).resume_countdown();
+            0
+        }
+
         w32wm::WM_LBUTTONDOWN => {
+            let userdata = userdata as *mut WindowData;
+
             let (x, y) = (GET_X_LPARAM(lparam), GET_Y_LPARAM(lparam));
 
-            if util::rect_contains(CLOSE_BTN_RECT_EXTRA, x as i32, y as i32) {
-                close_notification(hwnd)
+            let close_btn_rect = (*userdata).metrics.close_btn_hit_rect();
+            if util::rect_contains(close_btn_rect, x as i32, y as i32) {
+                close_notification(hwnd, CloseReason::Dismissed)
+            } else if (*userdata)
+                .layout
+                .reply
+                .map_or(false, |rc| util::rect_contains(rc, x as i32, y as i32))
+            {
+                // around the edit control
+                SetFocus((*userdata).reply_input);
+            } else if (*userdata).more_at(x as i32, y as i32) {
+                expand_burst(hwnd);
+            } else if let Some(i) = (*userdata).action_at(x as i32, y as i32) {
+                // the action is invoked when the button is released over it
+                (*userdata).pressed_action = Some(i);
+                SetCapture(hwnd);
+                InvalidateRect(hwnd, std::ptr::null(), 0);
+            } else {
+                if let Some(on_click) = &mut (*userdata).on_click {
+                    on_click();
+                }
+                close_notification(hwnd, CloseReason::Clicked)
+            }
+
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        w32wm::WM_LBUTTONUP => {
+            let userdata = userdata as *mut WindowData;
+
+            if let Some(i) = (*userdata).pressed_action.take() {
+                ReleaseCapture();
+                InvalidateRect(hwnd, std::ptr::null(), 0);
+
+                let (x, y) = (GET_X_LPARAM(lparam), GET_Y_LPARAM(lparam));
+                if (*userdata).action_at(x as i32, y as i32) == Some(i) {
+                    let actions = &(*userdata).notification.actions;
+                    let id = actions[i].id.clone();
+                    (*userdata).handle.set_invoked_action(&id);
+                    if let Some(on_action) = &mut (*userdata).on_action {
+                        on_action(&id);
+                    }
+                    close_notification(hwnd, CloseReason::ActionInvoked);
+                }
+            }
+
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        WM_SUBMIT_REPLY => {
+            let userdata = userdata as *mut WindowData;
+
+            let reply = util::window_text((*userdata).reply_input);
+            if !reply.trim().is_empty() && !(*userdata).closing {
+                (*userdata).handle.set_reply(&reply);
+                if let Some(on_reply) = &mut (*userdata).on_reply {
+                    on_reply(&reply);
+                }
+                close_notification(hwnd, CloseReason::Replied);
+            }
+            0
+        }
+
+        w32wm::WM_COMMAND => {
+            let userdata = userdata as *mut WindowData;
+
+            if lparam != 0 && lparam == (*userdata).reply_input {
+                match (wparam >> 16) as u32 & 0xFFFF {
+                    EN_SETFOCUS => {
+                        // the notification doesn't expire while the user types a reply
+                        (*userdata).reply_focused = true;
+                        (*userdata).pause_countdown();
+                    }
+                    EN_KILLFOCUS => {
+                        (*userdata).reply_focused = false;
+                        (*userdata).resume_countdown();
+                    }
+                    // the placeholder comes and goes with the text
+                    EN_CHANGE => {
+                        InvalidateRect((*userdata).reply_input, std::ptr::null(), 1);
+                    }
+                    _ => {}
+                }
+                0
+            } else {
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+        }
+
+        w32wm::WM_CTLCOLOREDIT => {
+            let userdata = userdata as *mut WindowData;
+            let theme = theme::paint_theme(&(*userdata).theme, &util::Win32SystemSettings);
+            let hdc = wparam as HDC;
+            SetTextColor(hdc, theme.body_color.to_colorref());
+            SetBkColor(hdc, theme.action_button_color.to_colorref());
+            SetDCBrushColor(hdc, theme.action_button_color.to_colorref());
+            GetStockObject(DC_BRUSH) as LRESULT
+        }
+
+        w32wm::WM_MOUSEACTIVATE => {
+            let userdata = userdata as *mut WindowData;
+
+            // only the reply input takes the focus, clicking elsewhere leaves the active window alone
+            let mut point = POINT { x: 0, y: 0 };
+            GetCursorPos(&mut point);
+            ScreenToClient(hwnd, &mut point);
+            match (*userdata).layout.reply {
+                Some(rc) if util::rect_contains(rc, point.x, point.y) => MA_ACTIVATE as LRESULT,
+                _ => MA_NOACTIVATE as LRESULT,
+            }
+        }
+
+        WM_UPDATE_NOTIFICATION => {
+            let userdata = userdata as *mut WindowData;
+
+            if let Some(notification) = (*userdata).handle.take_update() {
+                let current = &mut (*userdata).notification;
+                // the notification is resized when its content needs more or less room
+                let height = (*userdata).layout.height;
+                current.update_content(notification);
+                update_progress_timer(hwnd, current.progress.as_ref());
+                (*userdata).update_layout();
+                if (*userdata).layout.height != height {
+                    apply_metrics(hwnd, userdata, (*userdata).metrics);
+                } else {
+                    InvalidateRect(hwnd, std::ptr::null(), 1);
+                }
+            }
+
+            0
+        }
+
+        WM_COLLAPSE_NOTIFICATION => {
+            let userdata = userdata as *mut WindowData;
+            let (more, hidden) = (wparam, lparam != 0);
+
+            // a closing notification fades out where it is
+            if (*userdata).closing {
+                return 0;
+            }
+            if hidden {
+                ShowWindow(hwnd, SW_HIDE);
+            } else if IsWindowVisible(hwnd) == 0 {
+                ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            }
+            if (*userdata).more != more {
+                (*userdata).more = more;
+                apply_metrics(hwnd, userdata, (*userdata).metrics);
+            }
+            0
+        }
+
+        WM_REPLACE_NOTIFICATION => {
+            let userdata = userdata as *mut WindowData;
+
+            if let Some(notification) = (*userdata).handle.take_replacement() {
+                replace_notification(hwnd, userdata, notification);
+            }
+
+            0
+        }
+
+        w32wm::WM_CLOSE => {
+            let userdata = userdata as *mut WindowData;
+
+            // only the first close request counts, a `WM_CLOSE` sent by the system carries no reason
+            if (*userdata).close_reason.is_none() {
+                (*userdata).close_reason = CloseReason::from_wparam(wparam);
+            }
+
+            // closing early cancels the timeout, it must not restart when the mouse leaves
+            (*userdata).countdown = None;
+            KillTimer(hwnd, TIMEOUT_TIMER_ID);
+            untag_notification(hwnd);
+
+            if (*userdata).closing {
+                // already fading out
+                0
+            } else if animations_enabled() {
+                (*userdata).closing = true;
+                (*userdata).fade = Some(Fade {
+                    started: Instant::now(),
+                    alpha: Tween::new((*userdata).alpha.into(), 0, FADE_OUT),
+                });
+                // let the clicks go through the fading notification
+                let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_TRANSPARENT as isize);
+                start_animation_timer(hwnd);
+                0
+            } else {
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+        }
+
+        w32wm::WM_TIMER if wparam == TIMEOUT_TIMER_ID => {
+            KillTimer(hwnd, TIMEOUT_TIMER_ID);
+            // the window may be destroyed from here, `userdata` must not be used afterwards
+            close_notification(hwnd, CloseReason::TimedOut);
+            0
+        }
+
+        w32wm::WM_TIMER if wparam == PROGRESS_TIMER_ID => {
+            if let Some((_, bar_rc)) = (*(userdata as *mut WindowData)).layout.progress {
+                InvalidateRect(hwnd, &bar_rc, 0);
+            }
+            0
+        }
+
+        w32wm::WM_TIMER if wparam == ANIMATION_TIMER_ID => {
+            let userdata = userdata as *mut WindowData;
+
+            if let Some(movement) = (*userdata).movement {
+                let elapsed = movement.started.elapsed();
+                SetWindowPos(
+                    hwnd,
+                    0,
+                    movement.x.value(elapsed),
+                    movement.y.value(elapsed),
+                    0,
+                    0,
+                    SWP_NOACTIVATE | SWP_NOSIZE | SWP_NOZORDER,
+                );
+                if movement.x.is_finished(elapsed) {
+                    (*userdata).movement = None;
+
+                    // the DPI changes ignored on the way apply once the notification arrived
+                    let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+                    let dpi = util::get_monitor_dpi(monitor);
+                    if dpi != (*userdata).metrics.dpi {
+                        apply_metrics(hwnd, userdata, Metrics::new(dpi, &(*userdata).theme));
+                    }
+                }
+            }
+
+            if let Some(fade) = (*userdata).fade {
+                let elapsed = fade.started.elapsed();
+                let alpha = fade.alpha.value(elapsed) as u8;
+                (*userdata).alpha = alpha;
+                SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
+                if fade.alpha.is_finished(elapsed) {
+                    (*userdata).fade = None;
+                }
+            }
+
+            if (*userdata).movement.is_none() && (*userdata).fade.is_none() {
+                KillTimer(hwnd, ANIMATION_TIMER_ID);
+                if (*userdata).closing {
+                    DestroyWindow(hwnd);
+                }
+            }
+            0
+        }
+
+        WM_MOVE_NOTIFICATION => {
+            let userdata = userdata as *mut WindowData;
+            let (x, y) = (wparam as i32, lparam as i32);
+
+            if !animations_enabled() {
+                SetWindowPos(
+                    hwnd,
+                    0,
+                    x,
+                    y,
+                    0,
+                    0,
+                    SWP_NOACTIVATE | SWP_NOSIZE | SWP_NOZORDER,
+                );
+                return 0;
+            }
+
+            // already on its way there, e.g. sliding in
+            if let Some(movement) = (*userdata).movement {
+                if (movement.x.to, movement.y.to) == (x, y) {
+                    return 0;
+                }
+            }
+
+            let mut rect: RECT = std::mem::zeroed();
+            GetWindowRect(hwnd, &mut rect);
+            if (rect.left, rect.top) != (x, y) {
+                (*userdata).movement = Some(Movement {
+                    started: Instant::now(),
+                    x: Tween::new(rect.left, x, GLIDE),
+                    y: Tween::new(rect.top, y, GLIDE),
+                });
+                start_animation_timer(hwnd);
             }
+            0
+        }
+
+        w32wm::WM_DPICHANGED => {
+            let userdata = userdata as *mut WindowData;
+
+            // the notification may cross another monitor while it moves,
+            // the DPI of its destination is applied when it arrives
+            if (*userdata).movement.is_some() {
+                return 0;
+            }
+
+            let dpi = (wparam & 0xFFFF) as u32;
+            apply_metrics(hwnd, userdata, Metrics::new(dpi, &(*userdata).theme));
+            0
+        }
+
+        WM_REFRESH_THEME => {
+            refresh_theme(hwnd, userdata as *mut WindowData);
+            0
+        }
+
+        // the app mode or the accent color changed
+        w32wm::WM_SETTINGCHANGE if util::wide_eq(lparam as *const u16, "ImmersiveColorSet") => {
+            refresh_theme(hwnd, userdata as *mut WindowData);
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        // high contrast was turned on or off, the colors are read when painting
+        w32wm::WM_SETTINGCHANGE if wparam == SPI_SETHIGHCONTRAST as usize => {
+            InvalidateRect(hwnd, std::ptr::null(), 1);
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        w32wm::WM_SYSCOLORCHANGE => {
+            InvalidateRect(hwnd, std::ptr::null(), 1);
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        w32wm::WM_DWMCOLORIZATIONCOLORCHANGED => {
+            refresh_theme(hwnd, userdata as *mut WindowData);
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        WM_REPOSITION_NOTIFICATIONS => {
+            reposition_active_notifications();
+            0
+        }
+
+        w32wm::WM_DISPLAYCHANGE => {
+            refresh_monitors();
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
 
+        // the taskbar was moved or resized
+        w32wm::WM_SETTINGCHANGE if wparam == SPI_SETWORKAREA as usize => {
+            reposition_active_notifications();
             DefWindowProcW(hwnd, msg, wparam, lparam)
         }
 
         w32wm::WM_DESTROY => {
             let userdata = userdata as *mut WindowData;
-            drop(Box::from_raw(userdata));
+            let mut data = Box::from_raw(userdata);
+            stop_sound(hwnd, &data.notification);
+            if data.reply_font != 0 {
+                DeleteObject(data.reply_font);
+            }
+
+            let reason = data.close_reason.unwrap_or(CloseReason::Closed);
+            if matches!(reason, CloseReason::Dismissed | CloseReason::TimedOut) {
+                if let Some(on_dismiss) = data.on_dismiss.take() {
+                    on_dismiss(reason);
+                }
+            }
+            if let Some(on_close) = data.on_close.take() {
+                on_close(reason);
+            }
+            data.handle.set_closed(reason);
+            drop(data);
+
+            remove_notification(hwnd, reason);
 
             DefWindowProcW(hwnd, msg, wparam, lparam)
         }
@@ -0,0 +1,96 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// A progress bar shown below the body of a notification, see [`Notification::progress`](crate::Notification::progress).
+///
+/// ```
+/// # use win7_notifications::*;
+/// let progress = Progress::new(0.3).status("Uploading photos").label("3 of 10");
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// The completed fraction, from `0.0` to `1.0`, or `None` when the progress is indeterminate.
+    pub value: Option<f64>,
+    /// Shown above the left end of the bar.
+    pub status: String,
+    /// Shown above the right end of the bar, for example `"3 of 10"`.
+    pub label: String,
+}
+
+impl Progress {
+    /// A progress bar filled to `value`, clamped to `0.0..=1.0`.
+    pub fn new(value: f64) -> Progress {
+        Progress {
+            value: Some(clamp(value)),
+            status: String::new(),
+            label: String::new(),
+        }
+    }
+
+    /// A progress bar showing that work is ongoing without telling how much is done.
+    pub fn indeterminate() -> Progress {
+        Progress {
+            value: None,
+            status: String::new(),
+            label: String::new(),
+        }
+    }
+
+    /// Set the `status` text.
+    pub fn status(mut self, status: &str) -> Progress {
+        self.status = status.to_owned();
+        self
+    }
+
+    /// Set the `label` text.
+    pub fn label(mut self, label: &str) -> Progress {
+        self.label = label.to_owned();
+        self
+    }
+
+    pub(crate) fn is_indeterminate(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Returns the width of the filled part of a determinate bar `width` pixels wide.
+    pub(crate) fn filled_width(&self, width: i32) -> i32 {
+        let value = self.value.map(clamp).unwrap_or(0.0);
+        (width as f64 * value).round() as i32
+    }
+}
+
+fn clamp(value: f64) -> f64 {
+    // `f64::clamp` keeps NaN
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_is_clamped() {
+        assert_eq!(Progress::new(1.5).value, Some(1.0));
+        assert_eq!(Progress::new(-1.0).value, Some(0.0));
+        assert_eq!(Progress::new(f64::NAN).value, Some(0.0));
+        assert!(Progress::indeterminate().is_indeterminate());
+    }
+
+    #[test]
+    fn filled_width() {
+        assert_eq!(Progress::new(0.3).filled_width(328), 98);
+        assert_eq!(Progress::new(1.0).filled_width(328), 328);
+
+        // the public field may be set out of range
+        let mut progress = Progress::new(0.0);
+        progress.value = Some(2.0);
+        assert_eq!(progress.filled_width(328), 328);
+        assert_eq!(Progress::indeterminate().filled_width(328), 0);
+    }
+}
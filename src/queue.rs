@@ -0,0 +1,136 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::urgency::Urgency;
+
+/// Notifications waiting for room on the screen, the most urgent ones leave first
+/// and notifications of the same urgency leave in the order they arrived.
+#[derive(Debug)]
+pub(crate) struct Queue<T> {
+    items: Vec<(Urgency, T)>,
+}
+
+impl<T> Queue<T> {
+    pub(crate) fn new() -> Queue<T> {
+        Queue { items: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, urgency: Urgency, item: T) {
+        self.items.push((urgency, item));
+    }
+
+    /// Puts back an item before the others of the same urgency.
+    pub(crate) fn push_front(&mut self, urgency: Urgency, item: T) {
+        self.items.insert(0, (urgency, item));
+    }
+
+    /// Removes and returns the next item to show.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.next_index().map(|i| self.items.remove(i).1)
+    }
+
+    /// Returns the next item to show, leaving it in the queue.
+    pub(crate) fn peek(&self) -> Option<&T> {
+        self.next_index().map(|i| &self.items[i].1)
+    }
+
+    /// Returns the index of the first of the most urgent items.
+    fn next_index(&self) -> Option<usize> {
+        let mut next: Option<(usize, Urgency)> = None;
+        for (i, (urgency, _)) in self.items.iter().enumerate() {
+            if next.map_or(true, |(_, best)| *urgency > best) {
+                next = Some((i, *urgency));
+            }
+        }
+        next.map(|(i, _)| i)
+    }
+
+    /// Returns the first item matching `predicate`.
+    pub(crate) fn find_mut(&mut self, predicate: impl Fn(&T) -> bool) -> Option<&mut T> {
+        self.items
+            .iter_mut()
+            .map(|(_, item)| item)
+            .find(|item| predicate(item))
+    }
+
+    /// Removes and returns the items matching `predicate`, in their arrival order.
+    pub(crate) fn remove_where(&mut self, predicate: impl Fn(&T) -> bool) -> Vec<T> {
+        let (removed, kept) = self.items.drain(..).partition(|(_, item)| predicate(item));
+        self.items = kept;
+        removed.into_iter().map(|(_, item)| item).collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_in_first_out() {
+        let mut queue = Queue::new();
+        queue.push(Urgency::Normal, 1);
+        queue.push(Urgency::Normal, 2);
+        queue.push(Urgency::Normal, 3);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        queue.push(Urgency::Normal, 4);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn peek_leaves_the_next_item() {
+        let mut queue = Queue::new();
+        assert_eq!(queue.peek(), None);
+        queue.push(Urgency::Low, 1);
+        queue.push(Urgency::Normal, 2);
+        assert_eq!(queue.peek(), Some(&2));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.peek(), Some(&1));
+    }
+
+    #[test]
+    fn requeued_items_stay_first() {
+        let mut queue = Queue::new();
+        queue.push(Urgency::Normal, 2);
+        queue.push(Urgency::Critical, 3);
+        queue.push_front(Urgency::Normal, 1);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+    }
+
+    #[test]
+    fn urgent_items_leave_first() {
+        let mut queue = Queue::new();
+        queue.push(Urgency::Low, "low");
+        queue.push(Urgency::Normal, "normal");
+        queue.push(Urgency::Critical, "critical 1");
+        queue.push(Urgency::Normal, "normal 2");
+        queue.push(Urgency::Critical, "critical 2");
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(
+            order,
+            ["critical 1", "critical 2", "normal", "normal 2", "low"]
+        );
+    }
+
+    #[test]
+    fn find_and_remove() {
+        let mut queue = Queue::new();
+        for i in 1..=5 {
+            queue.push(Urgency::Normal, i);
+        }
+        *queue.find_mut(|i| i % 2 == 0).unwrap() = 20;
+        assert_eq!(queue.remove_where(|i| i % 2 == 1), [1, 3, 5]);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(20));
+        assert_eq!(queue.pop(), Some(4));
+    }
+}
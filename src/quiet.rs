@@ -0,0 +1,246 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::urgency::Urgency;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A daily time range during which notifications are held back,
+/// see [`NotificationManager::set_quiet_hours`](crate::NotificationManager::set_quiet_hours).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Minutes since midnight, local time.
+    start: u32,
+    end: u32,
+}
+
+impl QuietHours {
+    /// Quiet from `start` until `end`, both given as `(hour, minute)` in local time.
+    ///
+    /// The range goes past midnight when `end` is before `start`, e.g. from `(22, 0)` to `(7, 0)`,
+    /// and is empty when they are equal. Times past `(23, 59)` wrap around.
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> QuietHours {
+        // reduced first so large values don't overflow
+        let minutes = |(hour, minute): (u32, u32)| {
+            ((hour % 24) * 60 + minute % MINUTES_PER_DAY) % MINUTES_PER_DAY
+        };
+        QuietHours {
+            start: minutes(start),
+            end: minutes(end),
+        }
+    }
+
+    /// Returns whether the range contains `minute`, counted from midnight.
+    pub(crate) fn contains(self, minute: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minute && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// What happens to the notifications shown during Do Not Disturb or quiet hours,
+/// see [`NotificationManager::set_quiet_mode`](crate::NotificationManager::set_quiet_mode).
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QuietMode {
+    /// The notifications are dropped, they are closed with [`CloseReason::Suppressed`](crate::CloseReason::Suppressed).
+    Suppress,
+    /// The notifications wait and are shown once the quiet time ends.
+    Queue,
+    /// Only [`Urgency::Critical`] notifications are shown, the others are dropped.
+    CriticalOnly,
+}
+
+impl Default for QuietMode {
+    fn default() -> Self {
+        QuietMode::Queue
+    }
+}
+
+/// What [`Notification::show`](crate::Notification::show) does with a notification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Show,
+    Queue,
+    Suppress,
+}
+
+/// Where the current time is read from.
+pub(crate) trait Clock {
+    /// The minutes since midnight, local time.
+    fn minute_of_day(&self) -> u32;
+}
+
+/// When notifications are held back.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct QuietSettings {
+    pub(crate) do_not_disturb: bool,
+    pub(crate) hours: Option<QuietHours>,
+    /// Whether notifications are held back while a full screen app or a presentation is running.
+    pub(crate) when_fullscreen: bool,
+    pub(crate) mode: QuietMode,
+}
+
+impl QuietSettings {
+    /// Returns whether notifications are held back now, `fullscreen` is only called when it matters.
+    pub(crate) fn is_quiet(&self, clock: &impl Clock, fullscreen: impl FnOnce() -> bool) -> bool {
+        self.do_not_disturb
+            || self
+                .hours
+                .map_or(false, |hours| hours.contains(clock.minute_of_day()))
+            || (self.when_fullscreen && fullscreen())
+    }
+
+    /// Returns what to do with a notification of `urgency` shown while it is `quiet`.
+    pub(crate) fn admit(&self, urgency: Urgency, quiet: bool) -> Admission {
+        if !quiet {
+            return Admission::Show;
+        }
+        match self.mode {
+            QuietMode::Suppress => Admission::Suppress,
+            QuietMode::Queue => Admission::Queue,
+            QuietMode::CriticalOnly if urgency == Urgency::Critical => Admission::Show,
+            QuietMode::CriticalOnly => Admission::Suppress,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A clock stopped at the given time.
+    struct FixedClock(u32, u32);
+
+    impl Clock for FixedClock {
+        fn minute_of_day(&self) -> u32 {
+            self.0 * 60 + self.1
+        }
+    }
+
+    fn quiet_at(settings: &QuietSettings, hour: u32, minute: u32) -> bool {
+        settings.is_quiet(&FixedClock(hour, minute), || false)
+    }
+
+    #[test]
+    fn hours_within_a_day() {
+        let hours = QuietHours::new((12, 30), (14, 0));
+        assert!(!hours.contains(12 * 60 + 29));
+        assert!(hours.contains(12 * 60 + 30));
+        assert!(hours.contains(13 * 60 + 59));
+        assert!(!hours.contains(14 * 60));
+    }
+
+    #[test]
+    fn hours_past_midnight() {
+        let hours = QuietHours::new((22, 0), (7, 0));
+        assert!(hours.contains(23 * 60));
+        assert!(hours.contains(0));
+        assert!(hours.contains(6 * 60 + 59));
+        assert!(!hours.contains(7 * 60));
+        assert!(!hours.contains(12 * 60));
+        assert_eq!(hours, QuietHours::new((46, 0), (6, 60)));
+        assert_eq!(
+            QuietHours::new((u32::MAX, u32::MAX), (0, 0)),
+            QuietHours::new((19, 15), (0, 0))
+        );
+        assert!(!QuietHours::new((8, 0), (8, 0)).contains(8 * 60));
+    }
+
+    #[test]
+    fn quiet_during_the_schedule() {
+        let settings = QuietSettings {
+            hours: Some(QuietHours::new((22, 0), (7, 0))),
+            ..QuietSettings::default()
+        };
+        assert!(quiet_at(&settings, 23, 15));
+        assert!(!quiet_at(&settings, 9, 0));
+        assert!(!quiet_at(&QuietSettings::default(), 23, 15));
+
+        let settings = QuietSettings {
+            do_not_disturb: true,
+            ..settings
+        };
+        assert!(quiet_at(&settings, 9, 0));
+    }
+
+    #[test]
+    fn quiet_while_fullscreen_if_enabled() {
+        let clock = FixedClock(9, 0);
+        let settings = QuietSettings::default();
+        assert!(!settings.is_quiet(&clock, || panic!("fullscreen isn't checked")));
+
+        let settings = QuietSettings {
+            when_fullscreen: true,
+            ..settings
+        };
+        assert!(settings.is_quiet(&clock, || true));
+        assert!(!settings.is_quiet(&clock, || false));
+    }
+
+    #[test]
+    fn held_notifications_follow_a_new_mode() {
+        let mut settings = QuietSettings::default();
+        assert_eq!(settings.admit(Urgency::Normal, true), Admission::Queue);
+
+        // the notifications held back are shown or dropped according to the new mode
+        settings.mode = QuietMode::Suppress;
+        assert_eq!(settings.admit(Urgency::Normal, true), Admission::Suppress);
+        assert_eq!(settings.admit(Urgency::Critical, true), Admission::Suppress);
+        settings.mode = QuietMode::CriticalOnly;
+        assert_eq!(settings.admit(Urgency::Normal, true), Admission::Suppress);
+        assert_eq!(settings.admit(Urgency::Critical, true), Admission::Show);
+        // and all of them once the quiet time ends
+        assert_eq!(settings.admit(Urgency::Normal, false), Admission::Show);
+    }
+
+    #[test]
+    fn replacements_are_admitted_like_new_notifications() {
+        // a notification replacing one on the screen during the quiet time is held back or dropped,
+        // the notification on the screen only changes when it is shown
+        let settings = QuietSettings {
+            do_not_disturb: true,
+            ..QuietSettings::default()
+        };
+        let quiet = settings.is_quiet(&FixedClock(9, 0), || false);
+        assert_eq!(settings.admit(Urgency::Normal, quiet), Admission::Queue);
+
+        let settings = QuietSettings {
+            mode: QuietMode::CriticalOnly,
+            ..settings
+        };
+        assert_eq!(settings.admit(Urgency::Normal, quiet), Admission::Suppress);
+        assert_eq!(settings.admit(Urgency::Critical, quiet), Admission::Show);
+    }
+
+    #[test]
+    fn admission_follows_the_mode() {
+        let admit = |mode, urgency| {
+            QuietSettings {
+                mode,
+                ..QuietSettings::default()
+            }
+            .admit(urgency, true)
+        };
+        assert_eq!(admit(QuietMode::Queue, Urgency::Critical), Admission::Queue);
+        assert_eq!(
+            admit(QuietMode::Suppress, Urgency::Normal),
+            Admission::Suppress
+        );
+        assert_eq!(
+            admit(QuietMode::CriticalOnly, Urgency::Critical),
+            Admission::Show
+        );
+        assert_eq!(
+            admit(QuietMode::CriticalOnly, Urgency::Low),
+            Admission::Suppress
+        );
+        assert_eq!(
+            QuietSettings::default().admit(Urgency::Low, false),
+            Admission::Show
+        );
+    }
+}
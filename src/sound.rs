@@ -0,0 +1,40 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+
+/// The sound played when a notification is shown, see [`Notification::sound`](crate::Notification::sound).
+///
+/// ```no_run
+/// # #[cfg(windows)] {
+/// # use win7_notifications::*;
+/// Notification::new()
+///     .summary("New message")
+///     .sound(Sound::Alias("Notification.IM".to_owned()))
+///     .show().unwrap();
+/// # }
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sound {
+    /// The default sound of the system, the critical stop sound for
+    /// [`Urgency::Critical`](crate::Urgency::Critical) notifications.
+    Default,
+
+    /// A sound of the system sound scheme, like `"SystemAsterisk"`, `"SystemExclamation"`
+    /// or `"Notification.IM"`.
+    Alias(String),
+
+    /// A WAV file.
+    File(PathBuf),
+
+    /// The content of a WAV file.
+    Memory(Vec<u8>),
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Sound::Default
+    }
+}
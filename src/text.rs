@@ -0,0 +1,171 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::mem;
+
+/// Appended to text cut to fit.
+pub(crate) const ELLIPSIS: &str = "…";
+
+/// Returns `text` on a single line, cut with an ellipsis if it is wider than `width`
+/// as measured by `measure`.
+pub(crate) fn truncate(text: &str, width: i32, measure: impl Fn(&str) -> i32) -> String {
+    if measure(text) <= width {
+        text.to_owned()
+    } else {
+        ellipsize(text, width, &measure)
+    }
+}
+
+/// Breaks `text` into lines no wider than `width` as measured by `measure`.
+///
+/// Each line of `text` starts a new line and words wider than `width` are broken anywhere.
+/// When there are more than `max_lines` lines, the extra ones are dropped and the last line
+/// kept ends with an ellipsis.
+pub(crate) fn wrap(
+    text: &str,
+    width: i32,
+    max_lines: Option<usize>,
+    measure: impl Fn(&str) -> i32,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        wrap_paragraph(paragraph, width, &measure, &mut lines);
+    }
+
+    if let Some(max_lines) = max_lines {
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(last) = lines.last_mut() {
+                *last = ellipsize(last, width, &measure);
+            }
+        }
+    }
+    lines
+}
+
+fn wrap_paragraph(
+    paragraph: &str,
+    width: i32,
+    measure: &impl Fn(&str) -> i32,
+    lines: &mut Vec<String>,
+) {
+    let start = lines.len();
+    let mut line = String::new();
+    for word in paragraph.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if measure(&candidate) <= width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(mem::take(&mut line));
+        }
+        let mut word = word;
+        while measure(word) > width {
+            let (head, tail) = word.split_at(fitting_prefix(word, width, measure));
+            lines.push(head.to_owned());
+            word = tail;
+        }
+        line = word.to_owned();
+    }
+    // an empty paragraph is an empty line
+    if !line.is_empty() || lines.len() == start {
+        lines.push(line);
+    }
+}
+
+/// Returns the longest prefix of `text` followed by an ellipsis that fits in `width`,
+/// the ellipsis alone if nothing fits.
+fn ellipsize(text: &str, width: i32, measure: &impl Fn(&str) -> i32) -> String {
+    let fits = |end: usize| measure(&format!("{}{}", text[..end].trim_end(), ELLIPSIS)) <= width;
+    let ends = char_ends(text);
+    // `fits` only turns false as the prefix grows
+    let count = ends.partition_point(|&end| fits(end));
+    let end = if count == 0 { 0 } else { ends[count - 1] };
+    format!("{}{}", text[..end].trim_end(), ELLIPSIS)
+}
+
+/// Returns the byte length of the longest prefix of `text` that fits in `width`,
+/// at least one character so breaking a word always makes progress.
+fn fitting_prefix(text: &str, width: i32, measure: &impl Fn(&str) -> i32) -> usize {
+    let ends = char_ends(text);
+    let count = ends.partition_point(|&end| measure(&text[..end]) <= width);
+    ends[count.max(1) - 1]
+}
+
+/// Returns the byte offsets of the end of each character of `text`.
+fn char_ends(text: &str) -> Vec<usize> {
+    text.char_indices().map(|(i, c)| i + c.len_utf8()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is one unit wide.
+    fn chars(text: &str) -> i32 {
+        text.chars().count() as i32
+    }
+
+    #[test]
+    fn short_text_is_not_truncated() {
+        assert_eq!(truncate("Hello", 5, chars), "Hello");
+        assert_eq!(truncate("", 5, chars), "");
+    }
+
+    #[test]
+    fn long_text_is_truncated_with_an_ellipsis() {
+        assert_eq!(truncate("Hello world", 8, chars), "Hello w…");
+        // no space before the ellipsis
+        assert_eq!(truncate("Hello world", 7, chars), "Hello…");
+        assert_eq!(truncate("Hello", 0, chars), "…");
+        assert_eq!(truncate("héllo wörld", 4, chars), "hél…");
+    }
+
+    #[test]
+    fn wraps_at_word_boundaries() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10, None, chars),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert!(wrap("", 10, None, chars).is_empty());
+    }
+
+    #[test]
+    fn keeps_paragraphs() {
+        assert_eq!(
+            wrap("first line\r\n\nsecond", 20, None, chars),
+            ["first line", "", "second"]
+        );
+    }
+
+    #[test]
+    fn breaks_long_words() {
+        assert_eq!(
+            wrap("a abcdefghij", 4, None, chars),
+            ["a", "abcd", "efgh", "ij"]
+        );
+        // a character wider than the line still goes on its own line
+        assert_eq!(wrap("ab", 0, None, chars), ["a", "b"]);
+    }
+
+    #[test]
+    fn limits_the_number_of_lines() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10, Some(2), chars),
+            ["the quick", "brown fox…"]
+        );
+        assert_eq!(
+            wrap("the quick brown fox jumps", 9, Some(2), chars),
+            ["the quick", "brown fo…"]
+        );
+        assert!(wrap("the quick", 10, Some(0), chars).is_empty());
+        assert_eq!(wrap("the quick", 10, Some(1), chars), ["the quick"]);
+    }
+}
@@ -0,0 +1,420 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// An RGB color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// Converts the color to a win32 `COLORREF`.
+    pub(crate) const fn to_colorref(self) -> u32 {
+        self.r as u32 | (self.g as u32) << 8 | (self.b as u32) << 16
+    }
+
+    /// Converts a win32 `COLORREF` to a color.
+    pub(crate) const fn from_colorref(colorref: u32) -> Color {
+        Color::rgb(
+            colorref as u8,
+            (colorref >> 8) as u8,
+            (colorref >> 16) as u8,
+        )
+    }
+
+    /// Mixes `percent`% of `other` into the color.
+    fn mix(self, other: Color, percent: u32) -> Color {
+        let channel =
+            |a: u8, b: u8| ((a as u32 * (100 - percent) + b as u32 * percent) / 100) as u8;
+        Color::rgb(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+
+    /// Returns whether dark text is more readable than light text on the color.
+    fn is_light(self) -> bool {
+        // perceived brightness, see https://www.w3.org/TR/AERT/#color-contrast
+        (self.r as u32 * 299 + self.g as u32 * 587 + self.b as u32 * 114) / 1000 > 128
+    }
+}
+
+const WHITE: Color = Color::rgb(255, 255, 255);
+const BLACK: Color = Color::rgb(0, 0, 0);
+
+/// Describes the colors, fonts and sizes of a notification.
+///
+/// Start from one of the presets and change the fields you need:
+///
+/// ```
+/// # use win7_notifications::*;
+/// let mut theme = Theme::light();
+/// theme.title_color = Color::rgb(0, 95, 184);
+/// theme.corner_radius = 8;
+/// ```
+///
+/// Sizes are in pixels at 100% scaling, they are scaled with the DPI of the monitor.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub background: Color,
+    pub appname_color: Color,
+    /// Color of the summary.
+    pub title_color: Color,
+    pub body_color: Color,
+    pub close_button_color: Color,
+    /// Color of the close button when the mouse hovers it.
+    pub close_button_hover_color: Color,
+    pub action_button_color: Color,
+    /// Color of an action button when the mouse hovers it.
+    pub action_button_hover_color: Color,
+    /// Color of an action button while it is pressed.
+    pub action_button_pressed_color: Color,
+    pub action_text_color: Color,
+    /// Color of the label of an action button when the mouse hovers it or while it is pressed.
+    pub action_text_hover_color: Color,
+    /// Color of the empty part of a [`Progress`](crate::Progress) bar.
+    pub progress_track_color: Color,
+    /// Color of the filled part of a [`Progress`](crate::Progress) bar.
+    pub progress_bar_color: Color,
+    /// Color of the stripe along the left edge of [`Urgency::Critical`](crate::Urgency::Critical) notifications.
+    pub critical_color: Color,
+    pub font_family: String,
+    pub appname_font_size: i32,
+    pub title_font_size: i32,
+    pub body_font_size: i32,
+    pub action_font_size: i32,
+    /// Font weight of the app name, from 100 (thin) to 900 (black), 400 being normal.
+    pub appname_font_weight: i32,
+    pub title_font_weight: i32,
+    pub body_font_weight: i32,
+    pub action_font_weight: i32,
+    /// Radius of the corners of the notification, `0` for square corners.
+    pub corner_radius: i32,
+    /// Space between the edges of the notification and its content.
+    pub margin: i32,
+}
+
+impl Theme {
+    /// The default theme, light text on a dark background.
+    pub fn dark() -> Theme {
+        Theme {
+            background: Color::rgb(50, 57, 69),
+            appname_color: Color::rgb(255, 255, 255),
+            title_color: Color::rgb(255, 255, 255),
+            body_color: Color::rgb(200, 200, 200),
+            close_button_color: Color::rgb(200, 200, 200),
+            close_button_hover_color: Color::rgb(255, 255, 255),
+            action_button_color: Color::rgb(66, 74, 89),
+            action_button_hover_color: Color::rgb(82, 91, 107),
+            action_button_pressed_color: Color::rgb(40, 46, 56),
+            action_text_color: Color::rgb(255, 255, 255),
+            action_text_hover_color: Color::rgb(255, 255, 255),
+            progress_track_color: Color::rgb(66, 74, 89),
+            progress_bar_color: Color::rgb(76, 194, 255),
+            critical_color: Color::rgb(255, 153, 164),
+            ..Theme::base()
+        }
+    }
+
+    /// Dark text on a light background.
+    pub fn light() -> Theme {
+        Theme {
+            background: Color::rgb(249, 249, 249),
+            appname_color: Color::rgb(26, 26, 26),
+            title_color: Color::rgb(0, 0, 0),
+            body_color: Color::rgb(96, 96, 96),
+            close_button_color: Color::rgb(96, 96, 96),
+            close_button_hover_color: Color::rgb(0, 0, 0),
+            action_button_color: Color::rgb(230, 230, 230),
+            action_button_hover_color: Color::rgb(218, 218, 218),
+            action_button_pressed_color: Color::rgb(204, 204, 204),
+            action_text_color: Color::rgb(26, 26, 26),
+            action_text_hover_color: Color::rgb(26, 26, 26),
+            progress_track_color: Color::rgb(218, 218, 218),
+            progress_bar_color: Color::rgb(0, 95, 184),
+            critical_color: Color::rgb(196, 43, 28),
+            ..Theme::base()
+        }
+    }
+
+    /// The fonts and sizes shared by the presets.
+    fn base() -> Theme {
+        Theme {
+            background: Color::rgb(0, 0, 0),
+            appname_color: Color::rgb(0, 0, 0),
+            title_color: Color::rgb(0, 0, 0),
+            body_color: Color::rgb(0, 0, 0),
+            close_button_color: Color::rgb(0, 0, 0),
+            close_button_hover_color: Color::rgb(0, 0, 0),
+            action_button_color: Color::rgb(0, 0, 0),
+            action_button_hover_color: Color::rgb(0, 0, 0),
+            action_button_pressed_color: Color::rgb(0, 0, 0),
+            action_text_color: Color::rgb(0, 0, 0),
+            action_text_hover_color: Color::rgb(0, 0, 0),
+            progress_track_color: Color::rgb(0, 0, 0),
+            progress_bar_color: Color::rgb(0, 0, 0),
+            critical_color: Color::rgb(0, 0, 0),
+            font_family: "Segoe UI".to_owned(),
+            appname_font_size: 15,
+            title_font_size: 17,
+            body_font_size: 17,
+            action_font_size: 15,
+            appname_font_weight: 400,
+            title_font_weight: 700,
+            body_font_weight: 400,
+            action_font_weight: 400,
+            corner_radius: 0,
+            margin: 16,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+/// How the theme of the notifications is chosen,
+/// see [`NotificationManager::set_theme_mode`](crate::NotificationManager::set_theme_mode).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeMode {
+    /// Always use the given theme.
+    Fixed(Theme),
+    /// Follow the light or dark app mode and the accent color of Windows,
+    /// shown notifications are repainted when they change.
+    ///
+    /// Falls back to [`Theme::dark`] on versions of Windows without an app mode.
+    System,
+}
+
+impl Default for ThemeMode {
+    fn default() -> Self {
+        ThemeMode::Fixed(Theme::default())
+    }
+}
+
+impl ThemeMode {
+    /// Returns the theme to use, reading `system` for [`ThemeMode::System`].
+    pub(crate) fn resolve(&self, system: &impl SystemSettings) -> Theme {
+        match self {
+            ThemeMode::Fixed(theme) => theme.clone(),
+            ThemeMode::System => system_theme(system),
+        }
+    }
+}
+
+/// The personalization settings of Windows the [`ThemeMode::System`] theme is built from.
+pub(crate) trait SystemSettings {
+    /// Whether apps should use the light theme, `None` if the setting is not available.
+    fn apps_use_light_theme(&self) -> Option<bool>;
+    /// The accent color, `None` if it is not available.
+    fn accent_color(&self) -> Option<Color>;
+    /// The system colors, `None` unless high contrast is on.
+    fn high_contrast_palette(&self) -> Option<SystemPalette>;
+}
+
+/// The system colors a theme is derived from in high contrast mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct SystemPalette {
+    pub(crate) window: Color,
+    pub(crate) window_text: Color,
+    pub(crate) highlight: Color,
+    pub(crate) highlight_text: Color,
+    pub(crate) button_face: Color,
+    pub(crate) button_text: Color,
+}
+
+/// Replaces the colors of `theme` with the system colors, keeping its fonts and sizes.
+pub(crate) fn high_contrast_theme(palette: SystemPalette, theme: &Theme) -> Theme {
+    Theme {
+        background: palette.window,
+        appname_color: palette.window_text,
+        title_color: palette.window_text,
+        body_color: palette.window_text,
+        close_button_color: palette.window_text,
+        close_button_hover_color: palette.highlight,
+        action_button_color: palette.button_face,
+        action_button_hover_color: palette.highlight,
+        action_button_pressed_color: palette.highlight,
+        action_text_color: palette.button_text,
+        action_text_hover_color: palette.highlight_text,
+        progress_track_color: palette.button_face,
+        progress_bar_color: palette.highlight,
+        critical_color: palette.highlight,
+        ..theme.clone()
+    }
+}
+
+/// Returns the theme to paint with, derived from the system colors when high contrast is on.
+pub(crate) fn paint_theme(theme: &Theme, system: &impl SystemSettings) -> Theme {
+    match system.high_contrast_palette() {
+        Some(palette) => high_contrast_theme(palette, theme),
+        None => theme.clone(),
+    }
+}
+
+/// Builds the theme matching the system settings, the action buttons use the accent color.
+pub(crate) fn system_theme(system: &impl SystemSettings) -> Theme {
+    let mut theme = if system.apps_use_light_theme() == Some(true) {
+        Theme::light()
+    } else {
+        Theme::dark()
+    };
+    if let Some(accent) = system.accent_color() {
+        theme.action_button_color = accent;
+        theme.action_button_hover_color = accent.mix(WHITE, 15);
+        theme.action_button_pressed_color = accent.mix(BLACK, 20);
+        theme.action_text_color = if accent.is_light() { BLACK } else { WHITE };
+        theme.action_text_hover_color = theme.action_text_color;
+        theme.progress_bar_color = accent;
+    }
+    theme
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeSettings {
+        light: Option<bool>,
+        accent: Option<Color>,
+        palette: Option<SystemPalette>,
+    }
+
+    const HIGH_CONTRAST_BLACK: SystemPalette = SystemPalette {
+        window: Color::rgb(0, 0, 0),
+        window_text: Color::rgb(255, 255, 255),
+        highlight: Color::rgb(26, 235, 255),
+        highlight_text: Color::rgb(0, 0, 0),
+        button_face: Color::rgb(0, 0, 0),
+        button_text: Color::rgb(255, 255, 255),
+    };
+
+    impl SystemSettings for FakeSettings {
+        fn apps_use_light_theme(&self) -> Option<bool> {
+            self.light
+        }
+
+        fn accent_color(&self) -> Option<Color> {
+            self.accent
+        }
+
+        fn high_contrast_palette(&self) -> Option<SystemPalette> {
+            self.palette
+        }
+    }
+
+    #[test]
+    fn colorref_is_bgr() {
+        assert_eq!(Color::rgb(0x12, 0x34, 0x56).to_colorref(), 0x0056_3412);
+        assert_eq!(
+            Color::from_colorref(0x0056_3412),
+            Color::rgb(0x12, 0x34, 0x56)
+        );
+    }
+
+    #[test]
+    fn follows_the_app_mode() {
+        let light = FakeSettings {
+            light: Some(true),
+            accent: None,
+            palette: None,
+        };
+        let dark = FakeSettings {
+            light: Some(false),
+            accent: None,
+            palette: None,
+        };
+        assert_eq!(system_theme(&light), Theme::light());
+        assert_eq!(system_theme(&dark), Theme::dark());
+    }
+
+    #[test]
+    fn falls_back_to_dark_without_app_mode() {
+        let system = FakeSettings {
+            light: None,
+            accent: None,
+            palette: None,
+        };
+        assert_eq!(system_theme(&system), Theme::dark());
+    }
+
+    #[test]
+    fn action_buttons_use_the_accent_color() {
+        let system = FakeSettings {
+            light: Some(true),
+            accent: Some(Color::rgb(0, 120, 215)),
+            palette: None,
+        };
+        let theme = system_theme(&system);
+        assert_eq!(theme.background, Theme::light().background);
+        assert_eq!(theme.action_button_color, Color::rgb(0, 120, 215));
+        assert_eq!(theme.action_button_hover_color, Color::rgb(38, 140, 221));
+        assert_eq!(theme.action_button_pressed_color, Color::rgb(0, 96, 172));
+        assert_eq!(theme.action_text_color, WHITE);
+
+        let system = FakeSettings {
+            light: Some(false),
+            accent: Some(Color::rgb(255, 185, 0)),
+            palette: None,
+        };
+        assert_eq!(system_theme(&system).action_text_color, BLACK);
+    }
+
+    #[test]
+    fn fixed_mode_ignores_the_system() {
+        let system = FakeSettings {
+            light: Some(true),
+            accent: Some(Color::rgb(0, 120, 215)),
+            palette: None,
+        };
+        let mode = ThemeMode::Fixed(Theme::dark());
+        assert_eq!(mode.resolve(&system), Theme::dark());
+        assert_eq!(ThemeMode::System.resolve(&system), system_theme(&system));
+    }
+
+    #[test]
+    fn high_contrast_uses_the_system_colors() {
+        let mut theme = Theme::light();
+        theme.font_family = "Consolas".to_owned();
+        theme.corner_radius = 8;
+
+        let hc = high_contrast_theme(HIGH_CONTRAST_BLACK, &theme);
+        assert_eq!(hc.background, Color::rgb(0, 0, 0));
+        assert_eq!(hc.title_color, Color::rgb(255, 255, 255));
+        assert_eq!(hc.body_color, Color::rgb(255, 255, 255));
+        assert_eq!(hc.close_button_hover_color, Color::rgb(26, 235, 255));
+        assert_eq!(hc.action_button_hover_color, Color::rgb(26, 235, 255));
+        assert_eq!(hc.action_text_hover_color, Color::rgb(0, 0, 0));
+        // only the colors change
+        assert_eq!(hc.font_family, "Consolas");
+        assert_eq!(hc.corner_radius, 8);
+    }
+
+    #[test]
+    fn paints_with_the_theme_unless_high_contrast() {
+        let mut system = FakeSettings {
+            light: None,
+            accent: None,
+            palette: None,
+        };
+        assert_eq!(paint_theme(&Theme::dark(), &system), Theme::dark());
+
+        system.palette = Some(HIGH_CONTRAST_BLACK);
+        assert_eq!(
+            paint_theme(&Theme::dark(), &system),
+            high_contrast_theme(HIGH_CONTRAST_BLACK, &Theme::dark())
+        );
+    }
+}
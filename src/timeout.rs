@@ -2,6 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use std::time::{Duration, Instant};
+
+/// The least time left to a notification when the mouse leaves it.
+pub(crate) const HOVER_GRACE_PERIOD: Duration = Duration::from_millis(2000);
+
 /// Describes the timeout of a notification
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Timeout {
@@ -31,3 +36,89 @@ impl Default for Timeout {
         Timeout::Default
     }
 }
+
+/// The countdown of a notification timeout, paused while the mouse hovers the notification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Countdown {
+    remaining: Duration,
+    /// When the countdown was last started or resumed, `None` while paused.
+    running_since: Option<Instant>,
+}
+
+impl Countdown {
+    /// Starts a countdown at `now`, returns `None` for [`Timeout::Never`].
+    pub(crate) fn start(timeout: Timeout, now: Instant) -> Option<Countdown> {
+        if timeout == Timeout::Never {
+            return None;
+        }
+        Some(Countdown {
+            remaining: Duration::from_millis(timeout.into()),
+            running_since: Some(now),
+        })
+    }
+
+    /// Returns the time left before the notification expires.
+    pub(crate) fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub(crate) fn pause(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.remaining = self
+                .remaining
+                .saturating_sub(now.saturating_duration_since(since));
+        }
+    }
+
+    /// Resumes a paused countdown, leaving at least the [`HOVER_GRACE_PERIOD`],
+    /// and returns the time left.
+    pub(crate) fn resume(&mut self, now: Instant) -> Duration {
+        if self.running_since.is_none() {
+            self.remaining = self.remaining.max(HOVER_GRACE_PERIOD);
+            self.running_since = Some(now);
+        }
+        self.remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn never_has_no_countdown() {
+        assert_eq!(Countdown::start(Timeout::Never, Instant::now()), None);
+    }
+
+    #[test]
+    fn pausing_keeps_the_remaining_time() {
+        let start = Instant::now();
+        let mut countdown = Countdown::start(Timeout::Milliseconds(8000), start).unwrap();
+        countdown.pause(start + ms(3000));
+        assert_eq!(countdown.remaining(), ms(5000));
+
+        // pausing twice doesn't count the paused time
+        countdown.pause(start + ms(4000));
+        assert_eq!(countdown.remaining(), ms(5000));
+
+        assert_eq!(countdown.resume(start + ms(10_000)), ms(5000));
+        countdown.pause(start + ms(11_000));
+        assert_eq!(countdown.remaining(), ms(4000));
+    }
+
+    #[test]
+    fn resuming_leaves_a_grace_period() {
+        let start = Instant::now();
+        let mut countdown = Countdown::start(Timeout::Default, start).unwrap();
+        countdown.pause(start + ms(4500));
+        assert_eq!(countdown.remaining(), ms(500));
+        assert_eq!(countdown.resume(start + ms(6000)), HOVER_GRACE_PERIOD);
+
+        // resuming a running countdown changes nothing
+        assert_eq!(countdown.resume(start + ms(7000)), HOVER_GRACE_PERIOD);
+    }
+}
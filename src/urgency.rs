@@ -0,0 +1,68 @@
+// Copyright 2020-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::timeout::Timeout;
+
+/// Describes how urgent a notification is, see [`Notification::urgency`](crate::Notification::urgency).
+///
+/// Urgencies are ordered from [`Urgency::Low`] to [`Urgency::Critical`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    /// Shown without a sound, and collapsed to its summary when
+    /// [`NotificationManager::set_collapse_low_urgency`](crate::NotificationManager::set_collapse_low_urgency) is enabled.
+    Low,
+
+    /// The default.
+    Normal,
+
+    /// Doesn't expire with [`Timeout::Default`], is drawn with an accent stripe, plays the
+    /// critical stop sound and is stacked closest to the corner, before other notifications.
+    Critical,
+}
+
+impl Default for Urgency {
+    fn default() -> Self {
+        Urgency::Normal
+    }
+}
+
+impl Urgency {
+    /// Returns the timeout of a notification with this urgency,
+    /// only an explicit timeout closes a critical notification.
+    pub(crate) fn timeout(self, timeout: Timeout) -> Timeout {
+        match (self, timeout) {
+            (Urgency::Critical, Timeout::Default) => Timeout::Never,
+            _ => timeout,
+        }
+    }
+
+    /// Returns whether a notification with this urgency is shown without a sound.
+    pub(crate) fn is_silent(self, silent: bool) -> bool {
+        silent || self == Urgency::Low
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn critical_notifications_ignore_the_default_timeout() {
+        assert_eq!(Urgency::Critical.timeout(Timeout::Default), Timeout::Never);
+        assert_eq!(
+            Urgency::Critical.timeout(Timeout::Milliseconds(100)),
+            Timeout::Milliseconds(100)
+        );
+        assert_eq!(Urgency::Low.timeout(Timeout::Default), Timeout::Default);
+        assert_eq!(Urgency::Normal.timeout(Timeout::Default), Timeout::Default);
+    }
+
+    #[test]
+    fn low_notifications_are_silent() {
+        assert!(Urgency::Low.is_silent(false));
+        assert!(!Urgency::Normal.is_silent(false));
+        assert!(!Urgency::Critical.is_silent(false));
+        assert!(Urgency::Critical.is_silent(true));
+    }
+}
@@ -4,36 +4,49 @@
 
 use std::{cell::Cell, ffi::OsStr, iter::once, os::windows::prelude::OsStrExt, ptr};
 
-use windows_sys::Win32::{
-    Foundation::*,
-    Graphics::Gdi::*,
-    System::Com::*,
-    UI::WindowsAndMessaging::{self as w32wm, *},
+use once_cell::sync::Lazy;
+use windows_sys::{
+    core::HRESULT,
+    s, w,
+    Win32::{
+        Foundation::*,
+        Graphics::{Dwm::DwmGetColorizationColor, Gdi::*},
+        System::{Com::*, LibraryLoader::*, Registry::*, SystemInformation::GetLocalTime},
+        UI::{
+            Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
+            Shell::{
+                SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE,
+                QUNS_RUNNING_D3D_FULL_SCREEN,
+            },
+            WindowsAndMessaging::{self as w32wm, *},
+        },
+    },
 };
 
-use crate::definitions::*;
+use crate::{
+    definitions::*,
+    error::Error,
+    image::{self, Image},
+    metrics::BASE_DPI,
+    quiet::Clock,
+    theme::{Color, SystemPalette, SystemSettings},
+};
 
+/// Returns the file name of the current executable, or an empty string if it can't be determined.
 pub fn current_exe_name() -> String {
     std::env::current_exe()
-        .unwrap()
-        .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_owned()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default()
 }
 
 pub fn encode_wide(string: impl AsRef<OsStr>) -> Vec<u16> {
     string.as_ref().encode_wide().chain(once(0)).collect()
 }
 
-/// Implementation of the `RGB` macro.
-#[allow(non_snake_case)]
-#[inline]
-pub const fn RGB(r: u32, g: u32, b: u32) -> u32 {
-    r | g << 8 | b << 16
-}
-
 #[cfg(target_pointer_width = "32")]
 #[allow(non_snake_case)]
 pub fn GetWindowLongPtrW(window: HWND, index: WINDOW_LONG_PTR_INDEX) -> isize {
@@ -58,6 +71,18 @@ pub fn SetWindowLongPtrW(window: HWND, index: WINDOW_LONG_PTR_INDEX, value: isiz
     unsafe { w32wm::SetWindowLongPtrW(window, index, value) }
 }
 
+/// Returns whether the null-terminated wide string `wide` is equal to `string`.
+pub unsafe fn wide_eq(wide: *const u16, string: &str) -> bool {
+    if wide.is_null() {
+        return false;
+    }
+    // stops at the first difference, at the latest on the terminating null
+    encode_wide(string)
+        .iter()
+        .enumerate()
+        .all(|(i, c)| *wide.add(i) == *c)
+}
+
 /// Implementation of the `GET_X_LPARAM` macro.
 #[allow(non_snake_case)]
 #[inline]
@@ -77,7 +102,41 @@ pub unsafe fn primary_monitor() -> HMONITOR {
     MonitorFromPoint(pt, MONITOR_DEFAULTTOPRIMARY)
 }
 
-pub unsafe fn get_monitor_info(hmonitor: HMONITOR) -> MONITORINFOEXW {
+/// Returns the DPI of the monitor.
+///
+/// `GetDpiForMonitor` is loaded at runtime as it is only available since Windows 8.1,
+/// the system DPI is used on older versions.
+pub unsafe fn get_monitor_dpi(hmonitor: HMONITOR) -> u32 {
+    type GetDpiForMonitor = unsafe extern "system" fn(HMONITOR, i32, *mut u32, *mut u32) -> HRESULT;
+    const MDT_EFFECTIVE_DPI: i32 = 0;
+
+    static GET_DPI_FOR_MONITOR: Lazy<Option<GetDpiForMonitor>> = Lazy::new(|| unsafe {
+        let shcore = LoadLibraryW(w!("shcore.dll"));
+        if shcore == 0 {
+            return None;
+        }
+        GetProcAddress(shcore, s!("GetDpiForMonitor"))
+            .map(|f| std::mem::transmute::<_, GetDpiForMonitor>(f))
+    });
+
+    if let Some(get_dpi_for_monitor) = *GET_DPI_FOR_MONITOR {
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        if get_dpi_for_monitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) >= 0 {
+            return dpi_x;
+        }
+    }
+
+    let hdc = GetDC(0);
+    let dpi = GetDeviceCaps(hdc, LOGPIXELSX as _);
+    ReleaseDC(0, hdc);
+    if dpi > 0 {
+        dpi as u32
+    } else {
+        BASE_DPI
+    }
+}
+
+pub unsafe fn get_monitor_info(hmonitor: HMONITOR) -> Result<MONITORINFOEXW, Error> {
     let mut monitor_info = MONITORINFOEXW {
         szDevice: [0_u16; 32],
         monitorInfo: MONITORINFO {
@@ -98,62 +157,82 @@ pub unsafe fn get_monitor_info(hmonitor: HMONITOR) -> MONITORINFOEXW {
         },
     };
     monitor_info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
-    GetMonitorInfoW(
+    if GetMonitorInfoW(
         hmonitor,
         &mut monitor_info as *mut MONITORINFOEXW as *mut MONITORINFO,
-    );
-    monitor_info
+    ) == 0
+    {
+        return Err(Error::MonitorInfo);
+    }
+    Ok(monitor_info)
 }
 
-struct ComInitialized(*mut ());
+/// Keeps COM initialized on the current thread, holds the result of `CoInitializeEx`.
+struct ComInitialized(HRESULT);
 impl Drop for ComInitialized {
     fn drop(&mut self) {
-        unsafe { CoUninitialize() };
+        // only a successful initialization must be balanced
+        if self.0 >= 0 {
+            unsafe { CoUninitialize() };
+        }
     }
 }
 
 thread_local! {
   static COM_INITIALIZED: ComInitialized = {
-    unsafe {
-        CoInitializeEx(ptr::null(), COINIT_APARTMENTTHREADED as _);
-        ComInitialized(ptr::null_mut())
-    }
+    unsafe { ComInitialized(CoInitializeEx(ptr::null(), COINIT_APARTMENTTHREADED as _)) }
   };
 
   static TASKBAR_LIST: Cell<*mut ITaskbarList> = Cell::new(ptr::null_mut());
 }
 
-pub unsafe fn skip_taskbar(hwnd: HWND) {
-    COM_INITIALIZED.with(|_| {});
+/// Returns the `ITaskbarList` of the current thread, creating it on first use.
+pub unsafe fn taskbar_list() -> Result<*mut ITaskbarList, Error> {
+    let hr = COM_INITIALIZED.with(|com| com.0);
+    // `RPC_E_CHANGED_MODE` means the application already initialized COM on this thread, which is fine.
+    if hr < 0 && hr != RPC_E_CHANGED_MODE {
+        return Err(Error::ComInit(hr));
+    }
 
     TASKBAR_LIST.with(|taskbar_list_ptr| {
         let mut taskbar_list = taskbar_list_ptr.get();
 
         if taskbar_list.is_null() {
-            CoCreateInstance(
+            let hr = CoCreateInstance(
                 &CLSID_TaskbarList,
                 ptr::null_mut(),
                 CLSCTX_ALL,
                 &IID_ITaskbarList,
                 &mut taskbar_list as *mut _ as *mut _,
             );
+            if hr < 0 {
+                return Err(Error::ComInit(hr));
+            }
 
             let hr_init = (*(*taskbar_list).lpVtbl).HrInit;
-            hr_init(taskbar_list.cast());
+            let hr = hr_init(taskbar_list.cast());
+            if hr < 0 {
+                let release = (*(*taskbar_list).lpVtbl).parent.Release;
+                release(taskbar_list.cast());
+                return Err(Error::ComInit(hr));
+            }
 
             taskbar_list_ptr.set(taskbar_list)
         }
 
-        taskbar_list = taskbar_list_ptr.get();
-        let delete_tab = (*(*taskbar_list).lpVtbl).DeleteTab;
-        delete_tab(taskbar_list, hwnd);
-    });
+        Ok(taskbar_list)
+    })
 }
 
-/// Returns a tuple of new and old `HFONT` handle
-pub unsafe fn set_font(hdc: HDC, name: &str, size: i32, weight: i32) -> (isize, isize) {
-    let name = format!("{}\0", name);
-    let hfont = CreateFontW(
+pub unsafe fn skip_taskbar(taskbar_list: *mut ITaskbarList, hwnd: HWND) {
+    let delete_tab = (*(*taskbar_list).lpVtbl).DeleteTab;
+    delete_tab(taskbar_list, hwnd);
+}
+
+/// Creates a font, it must be deleted with `DeleteObject`.
+pub unsafe fn create_font(name: &str, size: i32, weight: i32) -> HFONT {
+    let name = encode_wide(name);
+    CreateFontW(
         size,
         0,
         0,
@@ -168,50 +247,246 @@ pub unsafe fn set_font(hdc: HDC, name: &str, size: i32, weight: i32) -> (isize,
         CLEARTYPE_QUALITY as _,
         FF_DONTCARE as _,
         name.as_ptr() as _,
-    );
+    )
+}
+
+/// Returns a tuple of new and old `HFONT` handle
+pub unsafe fn set_font(hdc: HDC, name: &str, size: i32, weight: i32) -> (isize, isize) {
+    let hfont = create_font(name, size, weight);
     (hfont, SelectObject(hdc, hfont))
 }
 
-#[allow(dead_code)]
-pub(crate) struct Pixel {
-    pub(crate) r: u8,
-    pub(crate) g: u8,
-    pub(crate) b: u8,
-    pub(crate) a: u8,
+/// Draws `text` on a single line with its top left corner at `(x, y)`.
+pub unsafe fn text_out(hdc: HDC, x: i32, y: i32, text: &str) {
+    let text: Vec<u16> = OsStr::new(text).encode_wide().collect();
+    TextOutW(hdc, x, y, text.as_ptr(), text.len() as _);
+}
+
+/// Returns the text of a window, like the content of an edit control.
+pub unsafe fn window_text(hwnd: HWND) -> String {
+    let len = GetWindowTextLengthW(hwnd);
+    let mut text = vec![0u16; len as usize + 1];
+    let len = GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as _);
+    String::from_utf16_lossy(&text[..len.max(0) as usize])
+}
+
+/// Measures text drawn in a font, see [`set_font`].
+pub struct TextMeasurer {
+    hdc: HDC,
+    hfont: HFONT,
+    old_hfont: HGDIOBJ,
+}
+
+impl TextMeasurer {
+    pub unsafe fn new(name: &str, size: i32, weight: i32) -> TextMeasurer {
+        let hdc = CreateCompatibleDC(0);
+        let (hfont, old_hfont) = set_font(hdc, name, size, weight);
+        TextMeasurer {
+            hdc,
+            hfont,
+            old_hfont,
+        }
+    }
+
+    /// Returns the width of `text` drawn on a single line.
+    pub fn width(&self, text: &str) -> i32 {
+        let text: Vec<u16> = OsStr::new(text).encode_wide().collect();
+        let mut size = SIZE { cx: 0, cy: 0 };
+        unsafe { GetTextExtentPoint32W(self.hdc, text.as_ptr(), text.len() as _, &mut size) };
+        size.cx
+    }
+
+    /// Returns the distance between two lines of text, including the external leading.
+    pub fn line_height(&self) -> i32 {
+        let mut tm: TEXTMETRICW = unsafe { std::mem::zeroed() };
+        unsafe { GetTextMetricsW(self.hdc, &mut tm) };
+        tm.tmHeight + tm.tmExternalLeading
+    }
 }
 
-impl Pixel {
-    fn convert_to_bgra_mut(&mut self) {
-        std::mem::swap(&mut self.r, &mut self.b);
+impl Drop for TextMeasurer {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.hdc, self.old_hfont);
+            DeleteObject(self.hfont);
+            DeleteDC(self.hdc);
+        }
     }
 }
 
-pub(crate) const PIXEL_SIZE: usize = std::mem::size_of::<Pixel>();
+/// Checks that a buffer of `len` bytes holds a `width` x `height` 32bpp RGBA image.
+pub fn check_rgba(len: usize, width: u32, height: u32) -> Result<(), Error> {
+    if !image::is_rgba(len, width, height) {
+        return Err(Error::InvalidIcon { len, width, height });
+    }
+    Ok(())
+}
 
-pub fn get_hicon_from_32bpp_rgba(rgba: Vec<u8>, width: u32, height: u32) -> w32wm::HICON {
-    let mut rgba = rgba;
-    let pixel_count = rgba.len() / PIXEL_SIZE;
-    let mut and_mask = Vec::with_capacity(pixel_count);
-    let pixels =
-        unsafe { std::slice::from_raw_parts_mut(rgba.as_mut_ptr() as *mut Pixel, pixel_count) };
-    for pixel in pixels {
-        and_mask.push(pixel.a.wrapping_sub(std::u8::MAX)); // invert alpha channel
-        pixel.convert_to_bgra_mut();
+/// Draws `image` at `(x, y)` at its size, blending it with what is already drawn.
+pub unsafe fn draw_image(hdc: HDC, image: &Image, x: i32, y: i32) {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // top-down rows, like the rgba buffer
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            biSizeImage: 0,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: 0,
+            biClrImportant: 0,
+        },
+        bmiColors: [RGBQUAD {
+            rgbBlue: 0,
+            rgbGreen: 0,
+            rgbRed: 0,
+            rgbReserved: 0,
+        }],
+    };
+    let mut bits = ptr::null_mut();
+    let hbitmap = CreateDIBSection(hdc, &info, DIB_RGB_COLORS, &mut bits, 0, 0);
+    if hbitmap == 0 || bits.is_null() {
+        return;
     }
-    assert_eq!(and_mask.len(), pixel_count);
-    unsafe {
-        w32wm::CreateIcon(
-            HMODULE::default(),
-            width as i32,
-            height as i32,
-            1,
-            (4 * 8) as u8,
-            and_mask.as_ptr(),
-            rgba.as_ptr(),
-        ) as w32wm::HICON
+
+    // `AlphaBlend` expects premultiplied BGRA pixels
+    let rgba = image.rgba();
+    let bgra = std::slice::from_raw_parts_mut(bits as *mut u8, rgba.len());
+    for (dst, src) in bgra
+        .chunks_exact_mut(image::PIXEL_SIZE)
+        .zip(rgba.chunks_exact(image::PIXEL_SIZE))
+    {
+        let premultiply = |c: u8| (c as u32 * src[3] as u32 / 255) as u8;
+        dst[0] = premultiply(src[2]);
+        dst[1] = premultiply(src[1]);
+        dst[2] = premultiply(src[0]);
+        dst[3] = src[3];
     }
+
+    let hdc_mem = CreateCompatibleDC(hdc);
+    let old_hbitmap = SelectObject(hdc_mem, hbitmap);
+    let blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: u8::MAX,
+        AlphaFormat: AC_SRC_ALPHA as u8,
+    };
+    AlphaBlend(
+        hdc, x, y, width, height, hdc_mem, 0, 0, width, height, blend,
+    );
+    SelectObject(hdc_mem, old_hbitmap);
+    DeleteDC(hdc_mem);
+    DeleteObject(hbitmap);
+}
+
+/// Returns the `(width, height)` of the window.
+pub unsafe fn window_size(hwnd: HWND) -> (i32, i32) {
+    let mut rect = RECT {
+        bottom: 0,
+        left: 0,
+        right: 0,
+        top: 0,
+    };
+    GetWindowRect(hwnd, &mut rect);
+    (rect.right - rect.left, rect.bottom - rect.top)
 }
 
 pub fn rect_contains(rect: RECT, x: i32, y: i32) -> bool {
     (rect.left < x) && (x < rect.right) && (rect.top < y) && (y < rect.bottom)
 }
+
+/// Reads the personalization settings of Windows from the registry and DWM.
+pub(crate) struct Win32SystemSettings;
+
+impl SystemSettings for Win32SystemSettings {
+    fn apps_use_light_theme(&self) -> Option<bool> {
+        let mut value: u32 = 0;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+                w!("AppsUseLightTheme"),
+                RRF_RT_REG_DWORD,
+                ptr::null_mut(),
+                &mut value as *mut u32 as _,
+                &mut size,
+            )
+        };
+        // the value doesn't exist before Windows 10
+        if status == ERROR_SUCCESS {
+            Some(value != 0)
+        } else {
+            None
+        }
+    }
+
+    fn accent_color(&self) -> Option<Color> {
+        let (mut color, mut opaque) = (0, 0);
+        // fails when desktop composition is disabled
+        if unsafe { DwmGetColorizationColor(&mut color, &mut opaque) } < 0 {
+            return None;
+        }
+        // 0xAARRGGBB
+        Some(Color::rgb(
+            (color >> 16) as u8,
+            (color >> 8) as u8,
+            color as u8,
+        ))
+    }
+
+    fn high_contrast_palette(&self) -> Option<SystemPalette> {
+        let mut high_contrast = HIGHCONTRASTW {
+            cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+            dwFlags: 0,
+            lpszDefaultScheme: ptr::null_mut(),
+        };
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETHIGHCONTRAST,
+                high_contrast.cbSize,
+                &mut high_contrast as *mut HIGHCONTRASTW as _,
+                0,
+            )
+        };
+        if ok == 0 || high_contrast.dwFlags & HCF_HIGHCONTRASTON == 0 {
+            return None;
+        }
+
+        let color = |index| Color::from_colorref(unsafe { GetSysColor(index) });
+        Some(SystemPalette {
+            window: color(COLOR_WINDOW),
+            window_text: color(COLOR_WINDOWTEXT),
+            highlight: color(COLOR_HIGHLIGHT),
+            highlight_text: color(COLOR_HIGHLIGHTTEXT),
+            button_face: color(COLOR_BTNFACE),
+            button_text: color(COLOR_BTNTEXT),
+        })
+    }
+}
+
+/// Reads the local time of the system.
+pub(crate) struct LocalClock;
+
+impl Clock for LocalClock {
+    fn minute_of_day(&self) -> u32 {
+        let mut time: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        unsafe { GetLocalTime(&mut time) };
+        time.wHour as u32 * 60 + time.wMinute as u32
+    }
+}
+
+/// Returns whether a full screen app, a game or a presentation is running.
+pub(crate) fn is_fullscreen_app_running() -> bool {
+    let mut state = 0;
+    let hr = unsafe { SHQueryUserNotificationState(&mut state) };
+    hr >= 0
+        && matches!(
+            state,
+            QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE
+        )
+}